@@ -9,3 +9,119 @@ close-icon = Close
 minimize-icon = Minimize
 maximize-icon = Maximize
 back = Zurück zum vorherigen Bildschirm
+
+# Track card
+mute = M
+solo = S
+pan-left = L
+pan-right = R
+eq = EQ
+done = Fertig
+low-pass = Tiefpass
+high-pass = Hochpass
+eq-low = Tiefen
+eq-mid = Mitten
+eq-high = Höhen
+drift = Drift
+drift-speed = Tempo
+drift-random = Zufällig
+drift-wave = Welle
+off = Aus
+on = An
+reset = Zurücksetzen
+rate = Rate
+
+# Generator and granular controls
+carrier = Ton
+beat = Schwebung
+generator-binaural = Binaural
+generator-isochronic = Isochron
+generator-noise = Rauschen
+noise-white = Weißes Rauschen
+noise-pink = Rosa Rauschen
+noise-brown = Braunes Rauschen
+waveform-sine = Sinus
+waveform-triangle = Dreieck
+waveform-square = Rechteck
+band-delta = Delta
+band-theta = Theta
+band-alpha = Alpha
+band-beta = Beta
+granular = Granular
+grain = Korn
+density = Dichte
+scatter = Streuung
+detune = Verstimmung
+window = Fenster
+window-hann = Hann
+window-triangle = Dreieck
+window-tukey = Tukey
+
+# Player
+filter-placeholder = Nach Name, Kategorie oder Tag filtern
+playing-only = Nur laufende
+favorites-only = Nur Favoriten
+section-playing = { $playing }/{ $total } laufen
+pause-section = Abschnitt pausieren
+stop-section = Abschnitt stoppen
+dismiss = Schließen
+no-matches = Keine Spuren passen zum Filter
+tracks-active = { $active }/{ $total } aktiv
+minutes-short = { $minutes } Min.
+
+# Focus sessions
+focus = Fokus
+focus-break = Pause
+focus-phase-title = Zeit für { $phase }
+focus-back-to-work = Zurück an die Arbeit
+focus-cycles-today = Heute { $cycles } Fokuszyklen abgeschlossen
+focus-sessions = Fokussitzungen
+focus-work-length = Arbeit:
+focus-break-length = Pause:
+focus-work-preset = Voreinstellung für die Arbeit
+focus-break-preset = Voreinstellung für die Pause
+focus-chime = Signalton am Ende einer Phase
+focus-cycles = Abgeschlossene Zyklen: { $today } heute, { $total } insgesamt
+
+# Settings
+close-to-tray = Beim Schließen im Systembereich weiterspielen
+concurrency-policy = Wenn zu viele Spuren laufen:
+concurrency-refuse = Neue Spuren ablehnen
+concurrency-stop-oldest = Älteste Spur stoppen
+normalize = Lautheit der Spuren angleichen
+limiter = Hauptausgang begrenzen, um Übersteuerung zu vermeiden
+limiter-threshold = Limiter-Schwelle:
+
+# Export
+export-mix = Mix exportieren
+exporting = Exportiere...
+export-length = Exportlänge:
+export-fade = Ein- und ausblenden
+export-done = Mix exportiert nach { $path }
+
+# Alarms and schedule
+alarms = Wecker
+alarm-summary = { $days } · { $preset } · { $fade } Min. Einblendung
+alarm-fade = Einblendung:
+alarm-no-days = Ohne ausgewählten Tag klingelt der Wecker täglich
+add-alarm = Wecker hinzufügen
+every-day = Täglich
+preset = Voreinstellung
+add = Hinzufügen
+remove = Entfernen
+schedule-enabled = Voreinstellungen nach Tageszeit wechseln
+schedule-entry-sleep = { $time } { $preset } · Schlafen nach { $minutes } Min.
+schedule-sleep = Danach schlafen nach:
+never = nie
+crossfade = Überblendung:
+
+# Tray menu
+tray-pause-all = Alle pausieren
+tray-resume-all = Alle fortsetzen
+tray-master-volume = Gesamtlautstärke
+tray-presets = Voreinstellungen
+tray-sleep-timer = Schlaftimer
+tray-minutes = { $minutes } Minuten
+tray-hide = Fenster ausblenden
+tray-show = Fenster anzeigen
+tray-quit = Beenden
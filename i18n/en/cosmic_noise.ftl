@@ -9,3 +9,119 @@ close-icon = Close
 minimize-icon = Minimize
 maximize-icon = Maximize
 back = Back to previous screen
+
+# Track card
+mute = M
+solo = S
+pan-left = L
+pan-right = R
+eq = EQ
+done = Done
+low-pass = Low-pass
+high-pass = High-pass
+eq-low = Low
+eq-mid = Mid
+eq-high = High
+drift = Drift
+drift-speed = Speed
+drift-random = Random
+drift-wave = Wave
+off = Off
+on = On
+reset = Reset
+rate = Rate
+
+# Generator and granular controls
+carrier = Tone
+beat = Beat
+generator-binaural = Binaural
+generator-isochronic = Isochronic
+generator-noise = Noise
+noise-white = White noise
+noise-pink = Pink noise
+noise-brown = Brown noise
+waveform-sine = Sine
+waveform-triangle = Triangle
+waveform-square = Square
+band-delta = Delta
+band-theta = Theta
+band-alpha = Alpha
+band-beta = Beta
+granular = Granular
+grain = Grain
+density = Density
+scatter = Scatter
+detune = Detune
+window = Window
+window-hann = Hann
+window-triangle = Triangle
+window-tukey = Tukey
+
+# Player
+filter-placeholder = Filter by name, category or tag
+playing-only = Playing only
+favorites-only = Favorites only
+section-playing = { $playing }/{ $total } playing
+pause-section = Pause section
+stop-section = Stop section
+dismiss = Dismiss
+no-matches = No tracks match the filter
+tracks-active = { $active }/{ $total } active
+minutes-short = { $minutes } min
+
+# Focus sessions
+focus = Focus
+focus-break = Break
+focus-phase-title = { $phase } time
+focus-back-to-work = Back to work
+focus-cycles-today = { $cycles } focus cycles completed today
+focus-sessions = Focus sessions
+focus-work-length = Work:
+focus-break-length = Break:
+focus-work-preset = Work preset
+focus-break-preset = Break preset
+focus-chime = Chime when a phase ends
+focus-cycles = Completed cycles: { $today } today, { $total } in total
+
+# Settings
+close-to-tray = Keep playing in the tray when closed
+concurrency-policy = When too many tracks play:
+concurrency-refuse = Refuse new tracks
+concurrency-stop-oldest = Stop the oldest track
+normalize = Normalize loudness across tracks
+limiter = Limit the master output to prevent clipping
+limiter-threshold = Limiter threshold:
+
+# Export
+export-mix = Export mix
+exporting = Exporting...
+export-length = Export length:
+export-fade = Fade in and out
+export-done = Mix exported to { $path }
+
+# Alarms and schedule
+alarms = Alarms
+alarm-summary = { $days } · { $preset } · { $fade } min fade-in
+alarm-fade = Fade-in:
+alarm-no-days = No day selected rings every day
+add-alarm = Add alarm
+every-day = Every day
+preset = Preset
+add = Add
+remove = Remove
+schedule-enabled = Switch presets by time of day
+schedule-entry-sleep = { $time } { $preset } · sleep after { $minutes } min
+schedule-sleep = Then sleep after:
+never = never
+crossfade = Crossfade:
+
+# Tray menu
+tray-pause-all = Pause all
+tray-resume-all = Resume all
+tray-master-volume = Master volume
+tray-presets = Presets
+tray-sleep-timer = Sleep timer
+tray-minutes = { $minutes } minutes
+tray-hide = Hide window
+tray-show = Show window
+tray-quit = Quit
@@ -10,3 +10,119 @@ close-icon = Close
 minimize-icon = Minimize
 maximize-icon = Maximize
 back = Back to previous screen
+
+# Track card
+mute = M
+solo = S
+pan-left = V
+pan-right = H
+eq = EQ
+done = Klar
+low-pass = Lågpass
+high-pass = Högpass
+eq-low = Bas
+eq-mid = Mellan
+eq-high = Diskant
+drift = Drift
+drift-speed = Hastighet
+drift-random = Slumpvis
+drift-wave = Våg
+off = Av
+on = På
+reset = Återställ
+rate = Hastighet
+
+# Generator and granular controls
+carrier = Ton
+beat = Svävning
+generator-binaural = Binaural
+generator-isochronic = Isokron
+generator-noise = Brus
+noise-white = Vitt brus
+noise-pink = Rosa brus
+noise-brown = Brunt brus
+waveform-sine = Sinus
+waveform-triangle = Triangel
+waveform-square = Fyrkant
+band-delta = Delta
+band-theta = Theta
+band-alpha = Alfa
+band-beta = Beta
+granular = Granulär
+grain = Korn
+density = Täthet
+scatter = Spridning
+detune = Ostämning
+window = Fönster
+window-hann = Hann
+window-triangle = Triangel
+window-tukey = Tukey
+
+# Player
+filter-placeholder = Filtrera på namn, kategori eller tagg
+playing-only = Endast spelande
+favorites-only = Endast favoriter
+section-playing = { $playing }/{ $total } spelar
+pause-section = Pausa avsnitt
+stop-section = Stoppa avsnitt
+dismiss = Stäng
+no-matches = Inga spår matchar filtret
+tracks-active = { $active }/{ $total } aktiva
+minutes-short = { $minutes } min
+
+# Focus sessions
+focus = Fokus
+focus-break = Paus
+focus-phase-title = Dags för { $phase }
+focus-back-to-work = Tillbaka till arbetet
+focus-cycles-today = { $cycles } fokuscykler klara i dag
+focus-sessions = Fokuspass
+focus-work-length = Arbete:
+focus-break-length = Paus:
+focus-work-preset = Förval för arbete
+focus-break-preset = Förval för paus
+focus-chime = Ljudsignal när en fas tar slut
+focus-cycles = Klara cykler: { $today } i dag, { $total } totalt
+
+# Settings
+close-to-tray = Fortsätt spela i systemfältet när fönstret stängs
+concurrency-policy = När för många spår spelar:
+concurrency-refuse = Neka nya spår
+concurrency-stop-oldest = Stoppa det äldsta spåret
+normalize = Jämna ut ljudstyrkan mellan spår
+limiter = Begränsa huvudutgången för att undvika klippning
+limiter-threshold = Begränsarens tröskel:
+
+# Export
+export-mix = Exportera mix
+exporting = Exporterar...
+export-length = Exportlängd:
+export-fade = Tona in och ut
+export-done = Mixen exporterades till { $path }
+
+# Alarms and schedule
+alarms = Alarm
+alarm-summary = { $days } · { $preset } · { $fade } min intoning
+alarm-fade = Intoning:
+alarm-no-days = Utan vald dag ringer alarmet varje dag
+add-alarm = Lägg till alarm
+every-day = Varje dag
+preset = Förval
+add = Lägg till
+remove = Ta bort
+schedule-enabled = Byt förval efter tid på dygnet
+schedule-entry-sleep = { $time } { $preset } · sov efter { $minutes } min
+schedule-sleep = Sov sedan efter:
+never = aldrig
+crossfade = Övertoning:
+
+# Tray menu
+tray-pause-all = Pausa alla
+tray-resume-all = Återuppta alla
+tray-master-volume = Huvudvolym
+tray-presets = Förval
+tray-sleep-timer = Insomningstimer
+tray-minutes = { $minutes } minuter
+tray-hide = Dölj fönster
+tray-show = Visa fönster
+tray-quit = Avsluta
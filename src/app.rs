@@ -1,11 +1,48 @@
-use crate::audio::{AudioCommand, AudioSystem};
+use crate::audio::{AudioCommand, AudioSystem, db_to_percentage, percentage_to_db};
 use crate::config::ConfigManager;
-use crate::errors::AppError;
-use crate::models::{AppTheme, NoiseTrack, View};
+use crate::errors::{AppError, AudioError, ConfigError};
+use crate::export::{ExportJob, ExportOptions};
+use crate::fl;
+use crate::focus::{self, FocusHistory, FocusPhase, FocusSession};
+use crate::ipc::{self, Incoming, Request};
+use crate::keymap::{Action, Keymap};
+use crate::loudness;
+use crate::models::{
+    Alarm, AlarmDraft, AppTheme, AudioSettings, FocusSettings, MIN_VOLUME_DB, NoiseTrack, Preset,
+    Schedule, ScheduleDraft, ScheduleEntry, TrackFilter, TrackSection, View,
+};
+use crate::mpris;
+use crate::pcm::PcmOutput;
+use crate::tray::{self, Tray};
 
-use crate::utils::files;
-use iced::Task;
+use crate::utils::{dragwin, files};
+use chrono::{Local, NaiveDateTime, Timelike};
+use iced::futures::future::BoxFuture;
+use iced::keyboard::{self, Key, Modifiers};
+use iced::{Subscription, Task, window};
+use kira::sound::PlaybackState;
 use log::info;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// Volume change per key press, in percent
+const VOLUME_STEP_PERCENT: f32 = 5.0;
+
+// Output level ramp of an alarm, from silence to full volume
+#[derive(Debug, Clone, Copy)]
+struct AlarmFade {
+    started: Instant,
+    duration: Duration,
+}
+
+impl AlarmFade {
+    // Output gain in dB at `now`
+    fn gain(&self, now: Instant) -> f32 {
+        let elapsed = now.duration_since(self.started);
+        let progress = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        MIN_VOLUME_DB * (1.0 - progress.min(1.0))
+    }
+}
 
 pub struct CosmicNoise {
     // Audio system for managing playback
@@ -14,21 +51,114 @@ pub struct CosmicNoise {
     pub track_list: Vec<NoiseTrack>,
     // Current error state, if any
     pub error: Option<AppError>,
+    // Short message shown above the grid, e.g. when the track limit is reached
+    pub notice: Option<String>,
     // Current view state
     pub current_view: View,
     // Current theme
     pub current_theme: AppTheme,
+    // Saved track mixes
+    pub presets: Vec<Preset>,
+    // When the sleep timer stops playback, if set
+    pub sleep_deadline: Option<Instant>,
+    // Wake-up alarms
+    pub alarms: Vec<Alarm>,
+    // Alarm being entered in the settings
+    pub alarm_draft: AlarmDraft,
+    // Fade-in of the alarm that rang last, while it lasts
+    alarm_fade: Option<AlarmFade>,
+    // Presets started at times of day
+    pub schedule: Schedule,
+    // Schedule entry being entered in the settings
+    pub schedule_draft: ScheduleDraft,
+    // Lengths and presets of the focus phases
+    pub focus_settings: FocusSettings,
+    // Running focus session, if any
+    pub focus: Option<FocusSession>,
+    // Completed focus cycles per day
+    pub focus_history: FocusHistory,
+    // Minute the alarms and the schedule were last checked, so each fires only once
+    clock_checked: Option<NaiveDateTime>,
+    // Keyboard shortcuts
+    pub keymap: Keymap,
+    // Track card selected with the keyboard
+    pub focused_track: Option<usize>,
+    // Track card showing its filter and EQ panel
+    pub details_track: Option<usize>,
+    // Filter applied to the track grid
+    pub filter: TrackFilter,
+    // Names of the tracks marked as favorite
+    pub favorites: Vec<String>,
+    // Categories whose section is collapsed
+    pub collapsed_sections: Vec<String>,
+    // Length, fade and format picked for the next export
+    pub export_options: ExportOptions,
+    // Whether a mix is being rendered to a file
+    pub exporting: bool,
+    // Whether loading the track list has finished, successfully or not
+    tracks_loaded: bool,
+    // Remote requests received before the track list was loaded
+    pending_requests: Vec<Incoming>,
+    // Hide to the tray instead of exiting when the window is closed
+    pub close_to_tray: bool,
+    // Whether the main window is currently shown
+    pub window_visible: bool,
+    // MPRIS2 interface on the session bus, if registration succeeded
+    mpris: Option<mpris::Server>,
+    // System tray icon, if the desktop supports StatusNotifierItem
+    pub tray: Option<Tray>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     DragWin(crate::utils::dragwin::Message),
     Loaded(Result<Vec<NoiseTrack>, AppError>),
+    Remote(Incoming),
+    Tick,
+    KeyPressed(Key, Modifiers),
 }
 
 impl CosmicNoise {
-    pub fn new() -> (Self, Task<Message>) {
-        let mut audio_system = AudioSystem::new().unwrap_or_default();
+    pub fn new(pcm_output: Option<PcmOutput>) -> (Self, Task<Message>) {
+        let app = Self::init(pcm_output);
+        let task = Task::perform(files::load_data(), Message::Loaded);
+
+        (app, task)
+    }
+
+    // Set up audio and configuration without loading tracks, shared with headless mode
+    pub fn init(pcm_output: Option<PcmOutput>) -> Self {
+        let mut app = Self::init_without_bus(pcm_output);
+        app.mpris = mpris::Server::start(ipc::sender())
+            .inspect_err(|e| log::warn!("Failed to register MPRIS interface: {e}"))
+            .ok();
+        let tray_state = tray::TrayState {
+            presets: app
+                .presets
+                .iter()
+                .map(|preset| preset.name.clone())
+                .collect(),
+            ..tray::TrayState::default()
+        };
+        app.tray = Tray::spawn(ipc::sender(), tray_state)
+            .inspect_err(|e| log::warn!("Failed to register tray icon: {e}"))
+            .ok();
+        app
+    }
+
+    // Like `init`, but without registering the MPRIS interface and the tray icon on
+    // the session bus
+    fn init_without_bus(pcm_output: Option<PcmOutput>) -> Self {
+        let (max_concurrent_tracks, concurrency_policy) = ConfigManager::load_concurrency();
+        let settings = AudioSettings {
+            max_concurrent_tracks,
+            concurrency_policy,
+            limiter: ConfigManager::load_limiter(),
+            normalize_audio: ConfigManager::load_normalize_audio(),
+            pcm_output,
+            ..AudioSettings::default()
+        };
+        let mut audio_system = AudioSystem::with_settings(settings).unwrap_or_default();
 
         let current_theme = ConfigManager::load_theme();
         info!("Loaded theme from configuration: {current_theme:?}");
@@ -38,17 +168,39 @@ impl CosmicNoise {
         audio_system.set_master_volume(master_volume);
         info!("Loaded master volume from configuration: {master_volume} dB");
 
-        let app = CosmicNoise {
+        CosmicNoise {
             audio_system,
             track_list: vec![],
             error: None,
+            notice: None,
             current_view: View::default(),
             current_theme,
-        };
-
-        let task = Task::perform(files::load_data(), Message::Loaded);
-
-        (app, task)
+            presets: ConfigManager::load_presets(),
+            sleep_deadline: None,
+            alarms: ConfigManager::load_alarms(),
+            alarm_draft: AlarmDraft::default(),
+            alarm_fade: None,
+            schedule: ConfigManager::load_schedule(),
+            schedule_draft: ScheduleDraft::default(),
+            focus_settings: ConfigManager::load_focus(),
+            focus: None,
+            focus_history: FocusHistory::load(),
+            clock_checked: None,
+            keymap: Keymap::new(&ConfigManager::load_keymap()),
+            focused_track: None,
+            details_track: None,
+            filter: TrackFilter::default(),
+            favorites: ConfigManager::load_favorites(),
+            collapsed_sections: ConfigManager::load_collapsed_sections(),
+            export_options: ExportOptions::default(),
+            exporting: false,
+            tracks_loaded: false,
+            pending_requests: vec![],
+            close_to_tray: ConfigManager::load_close_to_tray(),
+            window_visible: true,
+            mpris: None,
+            tray: None,
+        }
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
@@ -58,7 +210,13 @@ impl CosmicNoise {
             }
             Message::Loaded(result) => {
                 match result {
-                    Ok(tracks) => {
+                    Ok(mut tracks) => {
+                        tracks.push(NoiseTrack::generator(ConfigManager::load_generator()));
+                        let pans = ConfigManager::load_pans();
+                        for track in &mut tracks {
+                            track.favorite = self.favorites.contains(&track.name);
+                            track.pan = pans.get(&track.name).copied().unwrap_or_default();
+                        }
                         self.track_list = tracks;
                         self.error = None;
                     }
@@ -66,26 +224,649 @@ impl CosmicNoise {
                         self.error = Some(e);
                     }
                 }
+
+                // Run the requests that arrived while tracks were loading, once
+                self.tracks_loaded = true;
+                let pending = std::mem::take(&mut self.pending_requests);
+                let mut tasks: Vec<Task<Message>> = pending
+                    .into_iter()
+                    .map(|incoming| self.handle_incoming(incoming))
+                    .collect();
+                if let Some(analysis) = self.loudness_analysis() {
+                    tasks.push(Task::perform(analysis, |measured| {
+                        Message::DragWin(dragwin::Message::Audio(AudioCommand::SetLoudness(
+                            measured,
+                        )))
+                    }));
+                }
+                Task::batch(tasks)
+            }
+            Message::Remote(incoming) => {
+                // Requests about tracks wait until the tracks are loaded
+                if !self.tracks_loaded && incoming.request.needs_tracks() {
+                    self.pending_requests.push(incoming);
+                    return Task::none();
+                }
+                self.handle_incoming(incoming)
+            }
+            Message::Tick => {
+                if self
+                    .sleep_deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline)
+                {
+                    info!("Sleep timer expired, stopping all tracks");
+                    self.sleep_deadline = None;
+                    self.process_audio_command(AudioCommand::StopAll);
+                }
+                self.check_clock(Local::now().naive_local());
+                self.step_alarm_fade();
+                self.step_focus(Instant::now());
+                self.audio_system.step(Instant::now());
                 Task::none()
             }
+            Message::KeyPressed(key, modifiers) => match self.keymap.action(&key, modifiers) {
+                Some(action) => self.handle_action(action),
+                None => Task::none(),
+            },
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            Subscription::run(ipc::requests).map(Message::Remote),
+            keyboard::on_key_press(|key, modifiers| Some(Message::KeyPressed(key, modifiers))),
+        ];
+
+        let alarm_set = self.alarms.iter().any(|alarm| alarm.enabled);
+        let scheduled = self.schedule.enabled && !self.schedule.entries.is_empty();
+        let counting_down = self.sleep_deadline.is_some() || self.focus.is_some();
+        let fading = self.alarm_fade.is_some();
+        if counting_down || alarm_set || scheduled || fading || self.audio_system.needs_stepping() {
+            subscriptions.push(iced::time::every(Duration::from_secs(1)).map(|_| Message::Tick));
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    // Run a request and tell the client waiting for it, if any, how it went
+    fn handle_incoming(&mut self, incoming: Incoming) -> Task<Message> {
+        let (task, outcome) = match self.handle_request(incoming.request) {
+            Ok(task) => (task, Ok(())),
+            Err(reason) => {
+                log::warn!("Remote request failed: {reason}");
+                (Task::none(), Err(reason))
+            }
+        };
+        if let Some(reply) = incoming.reply {
+            reply.send(outcome);
+        }
+        task
+    }
+
+    // Handle a request coming from the control socket, D-Bus or the command line
+    pub fn handle_request(&mut self, request: Request) -> Result<Task<Message>, String> {
+        match request {
+            Request::Show => return Ok(self.show_window()),
+            Request::Hide => return Ok(self.hide_window()),
+            Request::Quit => return Ok(iced::exit()),
+            Request::Toggle(track) => {
+                let track_id = self.resolve_track(&track)?;
+                self.try_audio_command(AudioCommand::Play(track_id))?;
+            }
+            Request::SetVolume { track, percent } => {
+                let track_id = self.resolve_track(&track)?;
+                self.try_audio_command(AudioCommand::SetVolume {
+                    track_id,
+                    volume: percentage_to_db(percent),
+                })?;
+            }
+            Request::SetMaster(percent) => {
+                self.try_audio_command(AudioCommand::SetMasterVolume(percentage_to_db(percent)))?;
+            }
+            Request::PauseAll => self.try_audio_command(AudioCommand::PauseAll)?,
+            Request::ResumeAll => self.try_audio_command(AudioCommand::ResumeAll)?,
+            Request::StopAll => self.try_audio_command(AudioCommand::StopAll)?,
+            Request::ApplyPreset(name) => self.apply_preset(&name).map_err(|e| {
+                let reason = e.to_string();
+                self.error = Some(e);
+                reason
+            })?,
+            Request::SavePreset(name) => self.save_preset(name).map_err(|e| {
+                let reason = e.to_string();
+                self.error = Some(e);
+                reason
+            })?,
+            Request::SleepTimer(minutes) => {
+                self.sleep_deadline =
+                    minutes.map(|minutes| Instant::now() + Duration::from_secs(minutes * 60));
+                info!("Sleep timer set to {minutes:?} minutes");
+            }
+        }
+
+        Ok(Task::none())
+    }
+
+    // Show and focus the main window
+    pub fn show_window<T: Send + 'static>(&mut self) -> Task<T> {
+        self.window_visible = true;
+        self.publish_state();
+
+        window::get_latest()
+            .and_then(|id| window::set_mode(id, window::Mode::Windowed))
+            .chain(window::get_latest().and_then(|id| window::minimize(id, false)))
+            .chain(window::get_latest().and_then(window::gain_focus))
+    }
+
+    // Hide the main window, playback keeps running and the tray brings it back
+    pub fn hide_window<T: Send + 'static>(&mut self) -> Task<T> {
+        self.window_visible = false;
+        self.publish_state();
+
+        window::get_latest().and_then(|id| window::set_mode(id, window::Mode::Hidden))
+    }
+
+    // Run a keyboard shortcut
+    fn handle_action(&mut self, action: Action) -> Task<Message> {
+        // Navigation only moves between the cards the filter lets through
+        let visible = self.visible_tracks();
+        let position = self
+            .focused_track
+            .and_then(|focused| visible.iter().position(|index| *index == focused));
+
+        match action {
+            Action::TogglePause => {
+                let command = if matches!(self.audio_system.global_state(), PlaybackState::Playing)
+                {
+                    AudioCommand::PauseAll
+                } else {
+                    AudioCommand::ResumeAll
+                };
+                self.process_audio_command(command);
+            }
+            Action::SelectNext if !visible.is_empty() => {
+                self.focused_track = Some(match position {
+                    Some(position) => visible[(position + 1).min(visible.len() - 1)],
+                    None => visible[0],
+                });
+            }
+            Action::SelectPrevious if !visible.is_empty() => {
+                self.focused_track = Some(match position {
+                    Some(position) => visible[position.saturating_sub(1)],
+                    None => visible[visible.len() - 1],
+                });
+            }
+            Action::Select(position) if position < visible.len() => {
+                self.focused_track = Some(visible[position]);
+            }
+            Action::ToggleFocused => {
+                if let Some(track_id) = self.focused_track {
+                    self.process_audio_command(AudioCommand::Play(track_id));
+                }
+            }
+            Action::VolumeUp => self.step_volume(VOLUME_STEP_PERCENT),
+            Action::VolumeDown => self.step_volume(-VOLUME_STEP_PERCENT),
+            Action::Back => {
+                if self.current_view == View::Settings {
+                    self.current_view = View::Player;
+                } else {
+                    self.focused_track = None;
+                }
+            }
+            Action::Quit => return iced::exit(),
+            Action::SelectNext | Action::SelectPrevious | Action::Select(_) => {}
+        }
+
+        Task::none()
+    }
+
+    // Tracks passing the filter, grouped by category
+    pub fn sections(&self) -> Vec<TrackSection> {
+        let matching = (0..self.track_list.len())
+            .filter(|index| self.filter.matches(&self.track_list[*index]));
+        TrackSection::group(&self.track_list, matching)
+    }
+
+    pub fn is_collapsed(&self, section: &TrackSection) -> bool {
+        self.collapsed_sections
+            .iter()
+            .any(|name| name == section.name())
+    }
+
+    // Indices of the shown track cards, in the order they appear in the grid
+    pub fn visible_tracks(&self) -> Vec<usize> {
+        let sections = self.sections();
+        let grouped = TrackSection::is_grouped(&sections);
+
+        sections
+            .iter()
+            .filter(|section| !(grouped && self.is_collapsed(section)))
+            .flat_map(|section| section.tracks.iter().copied())
+            .collect()
+    }
+
+    // Collapse or expand a section and persist the choice
+    pub fn toggle_section(&mut self, name: &str) -> Result<(), AppError> {
+        if self
+            .collapsed_sections
+            .iter()
+            .any(|collapsed| collapsed == name)
+        {
+            self.collapsed_sections
+                .retain(|collapsed| collapsed != name);
+        } else {
+            self.collapsed_sections.push(name.to_string());
+        }
+        ConfigManager::save_collapsed_sections(&self.collapsed_sections)
+    }
+
+    // Run a command for every track of a section that is shown in the grid
+    pub fn command_section(&mut self, name: &str, command: fn(usize) -> AudioCommand) {
+        let Some(section) = self
+            .sections()
+            .into_iter()
+            .find(|section| section.name() == name)
+        else {
+            return;
+        };
+
+        for track_id in section.tracks {
+            if !matches!(self.track_list[track_id].state, PlaybackState::Stopped) {
+                self.process_audio_command(command(track_id));
+            }
+        }
+    }
+
+    // Background measurement of the tracks that have no loudness yet, if normalization is on
+    pub fn loudness_analysis(&self) -> Option<BoxFuture<'static, Vec<(usize, f32)>>> {
+        if !self.audio_system.normalize_audio() {
+            return None;
+        }
+
+        let pending: Vec<(usize, PathBuf)> = self
+            .track_list
+            .iter()
+            .enumerate()
+            .filter(|(_, track)| track.loudness.is_none())
+            // Folders of clips and synthesized tones have no single file, their level is
+            // left as is
+            .filter_map(|(index, track)| Some((index, track.source.file()?.to_path_buf())))
+            .collect();
+
+        (!pending.is_empty()).then(|| loudness::analyze(pending))
+    }
+
+    // Snapshot of the current mix to render, `None` while an export is already running
+    pub fn export_job(&mut self) -> Option<ExportJob> {
+        if self.exporting {
+            return None;
+        }
+
+        self.exporting = true;
+        Some(ExportJob::new(
+            &self.track_list,
+            self.audio_system.settings(),
+            self.export_options,
+        ))
+    }
+
+    // Ring the alarm or switch to the scheduled preset of the current minute, once per minute.
+    // An alarm wins over the schedule.
+    fn check_clock(&mut self, now: NaiveDateTime) {
+        let minute = now.with_second(0).unwrap_or(now);
+        if self.clock_checked == Some(minute) {
+            return;
+        }
+        self.clock_checked = Some(minute);
+
+        if let Some(alarm) = self
+            .alarms
+            .iter()
+            .find(|alarm| alarm.rings_at(now))
+            .cloned()
+        {
+            self.ring_alarm(&alarm);
+        } else if let Some(entry) = self.schedule.entry_at(now).cloned() {
+            self.run_schedule_entry(&entry);
         }
     }
 
+    // Crossfade to the scheduled preset
+    fn run_schedule_entry(&mut self, entry: &ScheduleEntry) {
+        info!("Schedule switching to {} at {}", entry.preset, entry.time);
+        let fade = Duration::from_secs(self.schedule.crossfade_seconds);
+        if let Err(e) = self.crossfade_to_preset(&entry.preset, fade) {
+            log::error!("Schedule could not start its preset: {e}");
+            self.notice = Some(e.to_string());
+            return;
+        }
+
+        if let Some(minutes) = entry.sleep_minutes {
+            self.sleep_deadline = Some(Instant::now() + Duration::from_secs(minutes * 60));
+            info!("Sleep timer set to {minutes} minutes by the schedule");
+        }
+        self.publish_state();
+    }
+
+    // Apply a preset with every volume change, start and stop spread over `fade`
+    pub fn crossfade_to_preset(&mut self, name: &str, fade: Duration) -> Result<(), AppError> {
+        self.audio_system.set_transition(Some(fade));
+        let result = self.apply_preset(name);
+        self.audio_system.set_transition(None);
+        result
+    }
+
+    // Start a focus session with a work phase, or end the running one
+    pub fn toggle_focus(&mut self) {
+        match self.focus.take() {
+            Some(_) => info!("Focus session stopped"),
+            None => self.start_focus_phase(FocusPhase::Work),
+        }
+    }
+
+    fn start_focus_phase(&mut self, phase: FocusPhase) {
+        info!("Starting {phase} phase");
+        self.focus = Some(FocusSession::start(
+            phase,
+            &self.focus_settings,
+            Instant::now(),
+        ));
+
+        if let Some(preset) = phase.preset(&self.focus_settings).map(str::to_string) {
+            let fade = Duration::from_secs(self.focus_settings.crossfade_seconds);
+            if let Err(e) = self.crossfade_to_preset(&preset, fade) {
+                log::error!("Focus session could not start its preset: {e}");
+                self.notice = Some(e.to_string());
+            }
+        }
+        self.publish_state();
+    }
+
+    // Move on to the next phase once the current one is over
+    fn step_focus(&mut self, now: Instant) {
+        let Some(session) = self.focus else {
+            return;
+        };
+        if !session.is_over(now) {
+            return;
+        }
+
+        let today = Local::now().date_naive();
+        if session.phase == FocusPhase::Work {
+            self.focus_history.record(today);
+            self.focus_history.save();
+        }
+
+        let next = session.phase.next();
+        if self.focus_settings.chime {
+            self.audio_system.play_one_shot(focus::chime());
+        }
+        let body = match next {
+            FocusPhase::Work => fl!("focus-back-to-work"),
+            FocusPhase::Break => fl!(
+                "focus-cycles-today",
+                cycles = self.focus_history.cycles_on(today)
+            ),
+        };
+        focus::notify(fl!("focus-phase-title", phase = next.to_string()), body);
+        self.start_focus_phase(next);
+    }
+
+    // Add the schedule entry entered in the settings, if it is complete
+    pub fn add_schedule_entry(&mut self) -> Result<(), AppError> {
+        let Some(entry) = self.schedule_draft.entry() else {
+            return Ok(());
+        };
+
+        self.schedule.insert(entry);
+        self.schedule_draft = ScheduleDraft::default();
+        ConfigManager::save_schedule(&self.schedule)
+    }
+
+    pub fn remove_schedule_entry(&mut self, index: usize) -> Result<(), AppError> {
+        if index < self.schedule.entries.len() {
+            self.schedule.entries.remove(index);
+        }
+        ConfigManager::save_schedule(&self.schedule)
+    }
+
+    // Start the alarm's preset from silence and fade it in
+    fn ring_alarm(&mut self, alarm: &Alarm) {
+        info!("Alarm {} rang, starting {}", alarm.time, alarm.preset);
+        self.process_audio_command(AudioCommand::StopAll);
+        let fade = Duration::from_secs(alarm.fade_minutes as u64 * 60);
+        if !fade.is_zero() {
+            self.audio_system
+                .set_output_gain(MIN_VOLUME_DB, Duration::ZERO);
+            self.alarm_fade = Some(AlarmFade {
+                started: Instant::now(),
+                duration: fade,
+            });
+        }
+
+        if let Err(e) = self.apply_preset(&alarm.preset) {
+            log::error!("Alarm could not start its preset: {e}");
+            self.notice = Some(e.to_string());
+            self.alarm_fade = None;
+            self.audio_system.set_output_gain(0.0, Duration::ZERO);
+        }
+        self.publish_state();
+    }
+
+    // Raise the output level a step further while an alarm fades in
+    fn step_alarm_fade(&mut self) {
+        let Some(fade) = &self.alarm_fade else {
+            return;
+        };
+
+        let gain = fade.gain(Instant::now());
+        // Each step is a one second tween, so the ramp is smooth between ticks
+        self.audio_system
+            .set_output_gain(gain, Duration::from_secs(1));
+        if gain >= 0.0 {
+            self.alarm_fade = None;
+        }
+    }
+
+    // Add the alarm entered in the settings, if it is complete
+    pub fn add_alarm(&mut self) -> Result<(), AppError> {
+        let Some(alarm) = self.alarm_draft.alarm() else {
+            return Ok(());
+        };
+
+        self.alarms.push(alarm);
+        self.alarms.sort_by_key(|alarm| alarm.time);
+        self.alarm_draft = AlarmDraft {
+            preset: self.alarm_draft.preset.take(),
+            ..AlarmDraft::default()
+        };
+        ConfigManager::save_alarms(&self.alarms)
+    }
+
+    // Enable or disable an alarm
+    pub fn set_alarm_enabled(&mut self, index: usize, enabled: bool) -> Result<(), AppError> {
+        if let Some(alarm) = self.alarms.get_mut(index) {
+            alarm.enabled = enabled;
+        }
+        ConfigManager::save_alarms(&self.alarms)
+    }
+
+    pub fn remove_alarm(&mut self, index: usize) -> Result<(), AppError> {
+        if index < self.alarms.len() {
+            self.alarms.remove(index);
+        }
+        ConfigManager::save_alarms(&self.alarms)
+    }
+
+    // Mark or unmark a track as favorite and persist the list
+    pub fn toggle_favorite(&mut self, track_id: usize) -> Result<(), AppError> {
+        let Some(track) = self.track_list.get_mut(track_id) else {
+            return Ok(());
+        };
+
+        track.favorite = !track.favorite;
+        if track.favorite {
+            self.favorites.push(track.name.clone());
+        } else {
+            self.favorites.retain(|name| *name != track.name);
+        }
+        ConfigManager::save_favorites(&self.favorites)
+    }
+
+    // Change the focused track volume, or the master volume when nothing is focused
+    fn step_volume(&mut self, percent: f32) {
+        match self.focused_track {
+            Some(track_id) => {
+                let current = db_to_percentage(self.track_list[track_id].volume_level);
+                self.process_audio_command(AudioCommand::SetVolume {
+                    track_id,
+                    volume: percentage_to_db(current + percent),
+                });
+            }
+            None => {
+                let current = db_to_percentage(self.audio_system.master_volume());
+                self.process_audio_command(AudioCommand::SetMasterVolume(percentage_to_db(
+                    current + percent,
+                )));
+            }
+        }
+    }
+
+    fn resolve_track(&self, track: &ipc::TrackRef) -> Result<usize, String> {
+        track
+            .resolve(&self.track_list)
+            .ok_or_else(|| format!("no track matches '{track}'"))
+    }
+
+    // Start the tracks of a preset at their saved volumes and stop everything else
+    pub fn apply_preset(&mut self, name: &str) -> Result<(), AppError> {
+        let preset = self
+            .presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or_else(|| AppError::Config(ConfigError::PresetNotFound(name.to_string())))?;
+
+        for track_id in 0..self.track_list.len() {
+            let state = self.track_list[track_id].state;
+            match preset.track(&self.track_list[track_id].name) {
+                Some(entry) => {
+                    self.process_audio_command(AudioCommand::SetVolume {
+                        track_id,
+                        volume: entry.volume,
+                    });
+                    self.process_audio_command(AudioCommand::SetPan {
+                        track_id,
+                        pan: entry.pan,
+                    });
+                    self.process_audio_command(AudioCommand::SetPlaybackRate {
+                        track_id,
+                        rate: entry.playback_rate,
+                    });
+                    self.process_audio_command(AudioCommand::SetTone {
+                        track_id,
+                        tone: entry.tone,
+                    });
+                    self.process_audio_command(AudioCommand::SetDrift {
+                        track_id,
+                        drift: entry.drift,
+                    });
+                    self.process_audio_command(AudioCommand::SetGranular {
+                        track_id,
+                        granular: entry.granular,
+                    });
+                    if let Some(generator) = entry.generator {
+                        self.process_audio_command(AudioCommand::SetGenerator {
+                            track_id,
+                            generator,
+                        });
+                    }
+                    if !matches!(state, PlaybackState::Playing) {
+                        self.process_audio_command(AudioCommand::Play(track_id));
+                    }
+                }
+                None if !matches!(state, PlaybackState::Stopped) => {
+                    self.process_audio_command(AudioCommand::Stop(track_id));
+                }
+                None => {}
+            }
+        }
+
+        info!("Applied preset: {}", preset.name);
+        Ok(())
+    }
+
+    // Save the currently playing tracks as a preset, replacing one with the same name
+    pub fn save_preset(&mut self, name: String) -> Result<(), AppError> {
+        let preset = Preset::from_tracks(name, &self.track_list);
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+        self.publish_state();
+        ConfigManager::save_presets(&self.presets)
+    }
+
     pub fn process_audio_command(&mut self, command: AudioCommand) {
-        match self
+        // Failures show up in the window, there is nobody else to tell
+        let _ = self.try_audio_command(command);
+    }
+
+    // Run an audio command, showing a failure in the window and returning it
+    fn try_audio_command(&mut self, command: AudioCommand) -> Result<(), String> {
+        let result = self
             .audio_system
-            .process_command(command, &mut self.track_list)
-        {
+            .process_command(command, &mut self.track_list);
+        let outcome = result.as_ref().map_err(ToString::to_string).copied();
+        match result {
             Ok(()) => {
                 // Clear any previous audio errors on success
                 if matches!(self.error, Some(AppError::Audio(_))) {
                     self.error = None;
                 }
             }
+            // Hitting the track limit is a notice, the grid stays usable
+            Err(e @ AppError::Audio(AudioError::TooManyTracks(_))) => {
+                log::warn!("{e}");
+                self.notice = Some(e.to_string());
+            }
             Err(e) => {
                 self.error = Some(e);
             }
         }
+
+        self.publish_state();
+        outcome
+    }
+
+    // Push the current state to the MPRIS interface and the tray menu
+    fn publish_state(&mut self) {
+        if let Some(server) = &self.mpris {
+            let state = mpris::PlayerState {
+                playback: self.audio_system.global_state(),
+                master_volume: self.audio_system.master_volume(),
+                active_tracks: self
+                    .track_list
+                    .iter()
+                    .filter(|track| matches!(track.state, PlaybackState::Playing))
+                    .map(|track| track.name.clone())
+                    .collect(),
+            };
+            if let Err(e) = server.update(&state) {
+                log::warn!("Failed to update MPRIS state: {e}");
+            }
+        }
+
+        if let Some(tray) = &mut self.tray {
+            tray.update(&tray::TrayState {
+                playback: self.audio_system.global_state(),
+                presets: self
+                    .presets
+                    .iter()
+                    .map(|preset| preset.name.clone())
+                    .collect(),
+                window_visible: self.window_visible,
+            });
+        }
     }
 }
 
@@ -95,8 +876,34 @@ impl Default for CosmicNoise {
             audio_system: AudioSystem::default(),
             track_list: vec![],
             error: None,
+            notice: None,
             current_view: View::default(),
             current_theme: ConfigManager::load_theme(),
+            presets: vec![],
+            sleep_deadline: None,
+            alarms: vec![],
+            alarm_draft: AlarmDraft::default(),
+            alarm_fade: None,
+            schedule: Schedule::default(),
+            schedule_draft: ScheduleDraft::default(),
+            focus_settings: FocusSettings::default(),
+            focus: None,
+            focus_history: FocusHistory::default(),
+            clock_checked: None,
+            keymap: Keymap::default(),
+            focused_track: None,
+            details_track: None,
+            filter: TrackFilter::default(),
+            favorites: vec![],
+            collapsed_sections: vec![],
+            export_options: ExportOptions::default(),
+            exporting: false,
+            tracks_loaded: false,
+            pending_requests: vec![],
+            close_to_tray: false,
+            window_visible: true,
+            mpris: None,
+            tray: None,
         }
     }
 }
@@ -104,11 +911,146 @@ impl Default for CosmicNoise {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::NaiveTime;
 
     #[test]
     fn test_app_creation() {
-        let (app, _task) = CosmicNoise::new();
+        let app = CosmicNoise::init_without_bus(None);
         assert!(app.track_list.is_empty());
         assert!(app.error.is_none());
+        assert!(app.mpris.is_none() && app.tray.is_none());
+    }
+
+    #[test]
+    fn test_navigation_follows_filter() {
+        let mut app = CosmicNoise::default();
+        app.track_list = ["rain", "waves", "rainforest"]
+            .iter()
+            .map(|name| NoiseTrack::new(name.to_string(), format!("/test/{name}.ogg").into()))
+            .collect();
+        app.filter.query = "rain".to_string();
+
+        assert_eq!(app.visible_tracks(), vec![0, 2]);
+
+        let _ = app.handle_action(Action::Select(1));
+        assert_eq!(app.focused_track, Some(2));
+        let _ = app.handle_action(Action::SelectPrevious);
+        assert_eq!(app.focused_track, Some(0));
+        let _ = app.handle_action(Action::SelectNext);
+        assert_eq!(app.focused_track, Some(2));
+    }
+
+    #[test]
+    fn test_apply_unknown_preset() {
+        let mut app = CosmicNoise::default();
+        assert_eq!(
+            app.apply_preset("missing"),
+            Err(AppError::Config(ConfigError::PresetNotFound(
+                "missing".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_failed_remote_requests() {
+        let mut app = CosmicNoise::default();
+        app.track_list = vec![NoiseTrack::new("rain".to_string(), "/test/rain.ogg".into())];
+
+        let toggle = app.handle_request(Request::Toggle("waves".into()));
+        assert!(matches!(toggle, Err(reason) if reason.contains("waves")));
+        let preset = app.handle_request(Request::ApplyPreset("missing".to_string()));
+        assert!(preset.is_err());
+        assert!(app.error.is_some());
+    }
+
+    #[test]
+    fn test_requests_wait_for_tracks() {
+        let mut app = CosmicNoise::default();
+        let _ = app.update(Message::Remote(Request::StopAll.into()));
+        assert_eq!(app.pending_requests.len(), 1);
+
+        // An empty track list still counts as loaded
+        let _ = app.update(Message::Loaded(Ok(vec![])));
+        assert!(app.pending_requests.is_empty());
+        let _ = app.update(Message::Remote(Request::StopAll.into()));
+        assert!(app.pending_requests.is_empty());
+    }
+
+    #[test]
+    fn test_alarm_rings_once() {
+        let mut app = CosmicNoise::default();
+        app.alarms = vec![Alarm {
+            time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            days: vec![],
+            preset: "missing".to_string(),
+            fade_minutes: 10,
+            enabled: true,
+        }];
+        let at = |time: &str| {
+            NaiveDateTime::parse_from_str(&format!("2024-01-01 {time}"), "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+        };
+
+        app.check_clock(at("06:59:59"));
+        assert!(app.notice.is_none());
+
+        // The preset doesn't exist, which shows up as a notice
+        app.check_clock(at("07:00:01"));
+        assert!(app.notice.is_some());
+        assert!(app.alarm_fade.is_none());
+
+        app.notice = None;
+        app.check_clock(at("07:00:30"));
+        assert!(app.notice.is_none());
+    }
+
+    #[test]
+    fn test_schedule_switches_preset() {
+        let mut app = CosmicNoise::default();
+        app.presets = vec![Preset {
+            name: "Brown noise".to_string(),
+            tracks: vec![],
+        }];
+        app.schedule.entries = vec![ScheduleEntry {
+            time: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            preset: "Brown noise".to_string(),
+            sleep_minutes: Some(45),
+        }];
+        let at = NaiveDateTime::parse_from_str("2024-01-01 23:00:05", "%Y-%m-%d %H:%M:%S");
+
+        app.check_clock(at.unwrap());
+        assert!(app.notice.is_none());
+        assert!(app.sleep_deadline.is_some());
+    }
+
+    #[test]
+    fn test_focus_phases() {
+        let mut app = CosmicNoise::default();
+        app.toggle_focus();
+        let session = app.focus.unwrap();
+        assert_eq!(session.phase, FocusPhase::Work);
+
+        // The phase keeps running until its end
+        app.step_focus(session.ends_at - Duration::from_secs(1));
+        assert_eq!(app.focus, Some(session));
+
+        app.toggle_focus();
+        assert!(app.focus.is_none());
+    }
+
+    #[test]
+    fn test_alarm_fade_gain() {
+        let started = Instant::now();
+        let fade = AlarmFade {
+            started,
+            duration: Duration::from_secs(60),
+        };
+
+        assert_eq!(fade.gain(started), MIN_VOLUME_DB);
+        assert_eq!(
+            fade.gain(started + Duration::from_secs(30)),
+            MIN_VOLUME_DB / 2.0
+        );
+        assert_eq!(fade.gain(started + Duration::from_secs(90)), 0.0);
     }
 }
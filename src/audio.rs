@@ -1,26 +1,222 @@
+use crate::ceiling::{CeilingBuilder, CeilingHandle};
+use crate::drift::{self, Drift};
 use crate::errors::{AppError, AudioError};
-use crate::models::{AudioSettings, NoiseTrack};
-use kira::sound::streaming::{StreamingSoundData, StreamingSoundHandle, StreamingSoundSettings};
-use kira::sound::{FromFileError, PlaybackState};
-use kira::{AudioManager, AudioManagerSettings, DefaultBackend, Tween};
+use crate::loudness;
+use crate::models::{
+    AudioSettings, ConcurrencyPolicy, DriftSettings, GeneratorSettings, GranularSettings,
+    LimiterSettings, MAX_CUTOFF_HZ, MAX_PLAYBACK_RATE, MAX_VOLUME_DB, MIN_CUTOFF_HZ,
+    MIN_PLAYBACK_RATE, MIN_VOLUME_DB, NoiseTrack, SoundSource, ToneSettings,
+};
+use crate::pcm::OutputBackend;
+use crate::source::{Output, Start, Voice};
+use kira::backend::Backend;
+use kira::effect::compressor::{CompressorBuilder, CompressorHandle};
+use kira::effect::eq_filter::{EqFilterBuilder, EqFilterHandle, EqFilterKind};
+use kira::effect::filter::{FilterBuilder, FilterHandle, FilterMode};
+use kira::sound::PlaybackState;
+use kira::sound::static_sound::StaticSoundData;
+use kira::track::{MainTrackBuilder, TrackBuilder, TrackHandle};
+use kira::{AudioManager, AudioManagerSettings, Decibels, Mix, Tween};
 use std::collections::HashMap;
-use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Centre frequencies of the three EQ bands, in Hz
+const EQ_LOW_HZ: f64 = 250.0;
+const EQ_MID_HZ: f64 = 1_000.0;
+const EQ_HIGH_HZ: f64 = 4_000.0;
+
+// Master limiter: a fast, high ratio compressor that only acts above the threshold, so
+// the mix is left untouched at normal levels, then a ceiling for what gets past it
+const LIMITER_RATIO: f64 = 20.0;
+const LIMITER_ATTACK: Duration = Duration::from_millis(5);
+const LIMITER_RELEASE: Duration = Duration::from_millis(200);
 
 pub struct AudioSystem {
-    manager: Option<AudioManager<DefaultBackend>>,
-    playing_handles: HashMap<usize, StreamingSoundHandle<FromFileError>>,
+    manager: Option<AudioManager<OutputBackend>>,
+    // Effects on the main track, present when the manager was created
+    master_bus: Option<MasterBus>,
+    playing_handles: HashMap<usize, Voice>,
+    // Sub-track with the filter and EQ effects of each track, created on first play
+    tone_chains: HashMap<usize, ToneChain>,
+    // Volume drift of the tone chains that have it enabled
+    drifts: HashMap<usize, Drift>,
+    // Tracks with an open stream, oldest first
+    start_order: Vec<usize>,
     global_state: PlaybackState,
     default_settings: AudioSettings,
+    // Fade used instead of `fade_duration` while crossfading to another preset
+    transition: Option<Duration>,
+    // When event, variation and drifting tracks were last stepped
+    last_step: Option<Instant>,
+}
+
+// Effects of the main track every sub-track ends up in
+pub struct MasterBus {
+    compressor: CompressorHandle,
+    ceiling: CeilingHandle,
+}
+
+impl MasterBus {
+    // Add the limiter effects to the main track builder
+    pub fn new(builder: &mut MainTrackBuilder, limiter: &LimiterSettings) -> Self {
+        let compressor = builder.add_effect(
+            CompressorBuilder::new()
+                .threshold(limiter.threshold as f64)
+                .ratio(LIMITER_RATIO)
+                .attack_duration(LIMITER_ATTACK)
+                .release_duration(LIMITER_RELEASE)
+                .mix(Self::mix(limiter)),
+        );
+        let ceiling = builder.add_effect(CeilingBuilder {
+            enabled: limiter.enabled,
+        });
+
+        Self {
+            compressor,
+            ceiling,
+        }
+    }
+
+    // A disabled compressor is kept in the chain but fully dry
+    fn mix(limiter: &LimiterSettings) -> Mix {
+        if limiter.enabled { Mix::WET } else { Mix::DRY }
+    }
+
+    fn apply(&mut self, limiter: &LimiterSettings, tween: Tween) {
+        self.compressor
+            .set_threshold(limiter.threshold as f64, tween);
+        self.compressor.set_mix(Self::mix(limiter), tween);
+        self.ceiling.set_enabled(limiter.enabled);
+    }
+}
+
+// A track's own mixer sub-track: high-pass -> low-pass -> low/mid/high EQ
+pub struct ToneChain {
+    pub track: TrackHandle,
+    high_pass: FilterHandle,
+    low_pass: FilterHandle,
+    low: EqFilterHandle,
+    mid: EqFilterHandle,
+    high: EqFilterHandle,
+}
+
+impl ToneChain {
+    pub fn new<B: Backend>(
+        manager: &mut AudioManager<B>,
+        tone: &ToneSettings,
+    ) -> Result<Self, AppError> {
+        let mut builder = TrackBuilder::new();
+        let high_pass = builder.add_effect(
+            FilterBuilder::new()
+                .mode(FilterMode::HighPass)
+                .cutoff(tone.high_pass as f64),
+        );
+        let low_pass = builder.add_effect(
+            FilterBuilder::new()
+                .mode(FilterMode::LowPass)
+                .cutoff(tone.low_pass as f64),
+        );
+        let low = builder.add_effect(EqFilterBuilder::new(
+            EqFilterKind::LowShelf,
+            EQ_LOW_HZ,
+            Decibels(tone.low),
+            1.0,
+        ));
+        let mid = builder.add_effect(EqFilterBuilder::new(
+            EqFilterKind::Bell,
+            EQ_MID_HZ,
+            Decibels(tone.mid),
+            1.0,
+        ));
+        let high = builder.add_effect(EqFilterBuilder::new(
+            EqFilterKind::HighShelf,
+            EQ_HIGH_HZ,
+            Decibels(tone.high),
+            1.0,
+        ));
+
+        let track = manager.add_sub_track(builder).map_err(|e| {
+            log::error!("Failed to create sub-track: {e}");
+            AppError::Audio(AudioError::HandleCreationFailed)
+        })?;
+
+        Ok(Self {
+            track,
+            high_pass,
+            low_pass,
+            low,
+            mid,
+            high,
+        })
+    }
+
+    // Move every effect to the new settings
+    fn apply(&mut self, tone: &ToneSettings, tween: Tween) {
+        self.high_pass.set_cutoff(tone.high_pass as f64, tween);
+        self.low_pass.set_cutoff(tone.low_pass as f64, tween);
+        self.low.set_gain(Decibels(tone.low), tween);
+        self.mid.set_gain(Decibels(tone.mid), tween);
+        self.high.set_gain(Decibels(tone.high), tween);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum AudioCommand {
     Play(usize),
-    SetVolume { track_id: usize, volume: f32 },
+    Pause(usize),
+    Stop(usize),
+    SetVolume {
+        track_id: usize,
+        volume: f32,
+    },
     StopAll,
+    // Stop all tracks with a custom fade-out duration
+    FadeOutAll(Duration),
     PauseAll,
     ResumeAll,
     SetMasterVolume(f32),
+    // Stereo position of a track, from -1.0 (left) to 1.0 (right)
+    SetPan {
+        track_id: usize,
+        pan: f32,
+    },
+    // Playback speed of a track, 1.0 is the recorded speed
+    SetPlaybackRate {
+        track_id: usize,
+        rate: f32,
+    },
+    // Filter and EQ settings of a track
+    SetTone {
+        track_id: usize,
+        tone: ToneSettings,
+    },
+    // Slow volume variation of a track
+    SetDrift {
+        track_id: usize,
+        drift: DriftSettings,
+    },
+    // Tone of the generator track
+    SetGenerator {
+        track_id: usize,
+        generator: GeneratorSettings,
+    },
+    // Play a track as a granular texture, or as a plain loop with `None`
+    SetGranular {
+        track_id: usize,
+        granular: Option<GranularSettings>,
+    },
+    // Master bus limiter settings
+    SetLimiter(LimiterSettings),
+    // What to do when the concurrent track limit is reached
+    SetConcurrencyPolicy(ConcurrencyPolicy),
+    // Turn loudness normalization on or off
+    SetNormalize(bool),
+    // Measured levels as `(track index, dBFS)` pairs
+    SetLoudness(Vec<(usize, f32)>),
+    // Silence a track without losing its volume or position
+    ToggleMute(usize),
+    // Silence every track that isn't soloed
+    ToggleSolo(usize),
 }
 
 impl AudioSystem {
@@ -31,19 +227,31 @@ impl AudioSystem {
 
     // Create a new audio system with custom settings
     pub fn with_settings(settings: AudioSettings) -> Result<Self, AppError> {
+        let mut main_track_builder = MainTrackBuilder::new();
+        let master_bus = MasterBus::new(&mut main_track_builder, &settings.limiter);
+
         let manager_settings = AudioManagerSettings {
             // Configure based on our settings
+            main_track_builder,
+            backend_settings: settings.pcm_output.clone(),
             ..AudioManagerSettings::default()
         };
 
-        let manager = AudioManager::<DefaultBackend>::new(manager_settings)
+        let manager = AudioManager::<OutputBackend>::new(manager_settings)
+            .inspect_err(|e| log::error!("Failed to open audio output: {e}"))
             .map_err(|_| AppError::Audio(AudioError::InitializationFailed))?;
 
         Ok(Self {
             manager: Some(manager),
+            master_bus: Some(master_bus),
             playing_handles: HashMap::new(),
+            tone_chains: HashMap::new(),
+            drifts: HashMap::new(),
+            start_order: vec![],
             global_state: PlaybackState::Stopped,
             default_settings: settings,
+            transition: None,
+            last_step: None,
         })
     }
 
@@ -55,11 +263,73 @@ impl AudioSystem {
             .unwrap_or(PlaybackState::Stopped)
     }
 
+    // Get the global playback state across all tracks
+    pub fn global_state(&self) -> PlaybackState {
+        self.global_state
+    }
+
+    // Gain of the main track on top of the mix, used to fade in alarms.
+    // Unlike the master volume it is never saved.
+    pub fn set_output_gain(&mut self, gain: f32, duration: Duration) {
+        let tween = Tween {
+            duration,
+            ..self.create_tween()
+        };
+        if let Some(manager) = &mut self.manager {
+            manager.main_track().set_volume(Decibels(gain), tween);
+        }
+    }
+
+    // Play a short sound on the main track, outside of the track list
+    pub fn play_one_shot(&mut self, sound: StaticSoundData) {
+        let Some(manager) = &mut self.manager else {
+            return;
+        };
+        if let Err(e) = manager.play(sound) {
+            log::error!("Failed to play sound: {e}");
+        }
+    }
+
+    // Make the following commands fade over `fade`, until it is reset with `None`
+    pub fn set_transition(&mut self, fade: Option<Duration>) {
+        self.transition = fade;
+    }
+
     // Get the current master volume
     pub fn master_volume(&self) -> f32 {
         self.default_settings.master_volume
     }
 
+    // Current audio settings, e.g. to render the mix offline
+    pub fn settings(&self) -> &AudioSettings {
+        &self.default_settings
+    }
+
+    // Number of tracks with an open stream, playing or paused
+    pub fn active_tracks(&self) -> usize {
+        self.playing_handles.len()
+    }
+
+    // Get the concurrent track limit
+    pub fn max_concurrent_tracks(&self) -> usize {
+        self.default_settings.max_concurrent_tracks
+    }
+
+    // Get the policy applied when the track limit is reached
+    pub fn concurrency_policy(&self) -> ConcurrencyPolicy {
+        self.default_settings.concurrency_policy
+    }
+
+    // Whether tracks are normalized to the same loudness
+    pub fn normalize_audio(&self) -> bool {
+        self.default_settings.normalize_audio
+    }
+
+    // Get the master bus limiter settings
+    pub fn limiter(&self) -> LimiterSettings {
+        self.default_settings.limiter
+    }
+
     // Set the master volume
     pub fn set_master_volume(&mut self, volume: f32) {
         self.default_settings.master_volume = volume;
@@ -75,12 +345,21 @@ impl AudioSystem {
             AudioCommand::Play(track_id) => {
                 self.play_track(track_id, tracks)?;
             }
+            AudioCommand::Pause(track_id) => {
+                self.pause_track(track_id, tracks)?;
+            }
+            AudioCommand::Stop(track_id) => {
+                self.stop_track(track_id, tracks)?;
+            }
             AudioCommand::SetVolume { track_id, volume } => {
                 self.set_track_volume(track_id, volume, tracks)?;
             }
             AudioCommand::StopAll => {
                 self.stop_all_tracks(tracks)?;
             }
+            AudioCommand::FadeOutAll(fade) => {
+                self.fade_out_all_tracks(fade, tracks)?;
+            }
             AudioCommand::PauseAll => {
                 self.pause_all_tracks(tracks)?;
             }
@@ -100,16 +379,94 @@ impl AudioSystem {
                 }
 
                 // Apply master volume to all currently playing tracks
+                self.apply_volumes(tracks);
+            }
+            AudioCommand::SetPan { track_id, pan } => {
+                self.set_track_pan(track_id, pan, tracks)?;
+            }
+            AudioCommand::SetPlaybackRate { track_id, rate } => {
+                self.set_track_playback_rate(track_id, rate, tracks)?;
+            }
+            AudioCommand::SetTone { track_id, tone } => {
+                self.set_track_tone(track_id, tone, tracks)?;
+            }
+            AudioCommand::SetDrift { track_id, drift } => {
+                let previous = std::mem::replace(&mut tracks[track_id].drift, drift);
+                if let Err(e) = self.apply_drift(track_id, tracks) {
+                    // The previous drift keeps running, show its settings again
+                    tracks[track_id].drift = previous;
+                    return Err(e);
+                }
+            }
+            AudioCommand::SetGenerator {
+                track_id,
+                generator,
+            } => {
+                if let SoundSource::Generator(settings) = &mut tracks[track_id].source {
+                    *settings = generator;
+                }
+                if let Some(Voice::Generator(handle)) = self.playing_handles.get_mut(&track_id) {
+                    handle.set_settings(generator);
+                }
+                if let Err(e) = crate::config::ConfigManager::save_generator(generator) {
+                    log::error!("Failed to save generator settings to configuration: {e}");
+                }
+            }
+            AudioCommand::SetGranular { track_id, granular } => {
+                self.set_track_granular(track_id, granular, tracks)?;
+            }
+            AudioCommand::SetLimiter(limiter) => {
+                self.default_settings.limiter = limiter;
                 let tween = self.create_tween();
-                for (track_id, handle) in self.playing_handles.iter_mut() {
-                    // Calculate effective volume: combine track volume with master volume
-                    // In dB, we add the values: track_volume + master_volume
-                    let effective_volume = tracks[*track_id].volume_level + volume;
-                    // Clamp to valid range
-                    let clamped_volume = effective_volume.clamp(-60.0, 0.0);
-                    handle.set_volume(clamped_volume, tween);
+                if let Some(master_bus) = &mut self.master_bus {
+                    master_bus.apply(&limiter, tween);
+                }
+                log::info!("Master limiter set to: {limiter:?}");
+
+                if let Err(e) = crate::config::ConfigManager::save_limiter(limiter) {
+                    log::error!("Failed to save limiter settings to configuration: {e}");
+                }
+            }
+            AudioCommand::SetConcurrencyPolicy(policy) => {
+                self.default_settings.concurrency_policy = policy;
+                log::info!("Concurrency policy set to: {policy:?}");
+
+                if let Err(e) = crate::config::ConfigManager::save_concurrency_policy(policy) {
+                    log::error!("Failed to save concurrency policy to configuration: {e}");
                 }
             }
+            AudioCommand::SetNormalize(enabled) => {
+                self.default_settings.normalize_audio = enabled;
+                self.apply_volumes(tracks);
+                log::info!("Loudness normalization: {enabled}");
+
+                if let Err(e) = crate::config::ConfigManager::save_normalize_audio(enabled) {
+                    log::error!("Failed to save normalization setting to configuration: {e}");
+                }
+            }
+            AudioCommand::SetLoudness(measured) => {
+                for (track_id, loudness) in measured {
+                    if let Some(track) = tracks.get_mut(track_id) {
+                        track.loudness = Some(loudness);
+                    }
+                }
+                self.apply_volumes(tracks);
+            }
+            AudioCommand::ToggleMute(track_id) => {
+                if let Some(track) = tracks.get_mut(track_id) {
+                    track.muted = !track.muted;
+                    log::info!("Track {} muted: {}", track.name, track.muted);
+                }
+                self.apply_volumes(tracks);
+            }
+            AudioCommand::ToggleSolo(track_id) => {
+                if let Some(track) = tracks.get_mut(track_id) {
+                    track.solo = !track.solo;
+                    log::info!("Track {} solo: {}", track.name, track.solo);
+                }
+                // Solo changes the gain of every other track too
+                self.apply_volumes(tracks);
+            }
         }
 
         Ok(())
@@ -148,7 +505,7 @@ impl AudioSystem {
             self.start_new_track(track_id, tracks)?;
         }
 
-        self.update_global_state();
+        self.update_global_state(tracks);
         Ok(())
     }
 
@@ -158,25 +515,37 @@ impl AudioSystem {
         track_id: usize,
         tracks: &mut [NoiseTrack],
     ) -> Result<(), AppError> {
-        let track_path = tracks[track_id].path.clone();
-        let track_volume = tracks[track_id].volume_level;
-        let track_name = tracks[track_id].name.clone();
+        self.make_room(tracks)?;
 
-        // Calculate effective volume: combine track volume with master volume
-        let effective_volume = track_volume + self.default_settings.master_volume;
-        // Clamp to valid range
-        let clamped_volume = effective_volume.clamp(-60.0, 0.0);
+        let track_name = tracks[track_id].name.clone();
+        let start = Start {
+            volume: self.effective_volume(track_id, tracks),
+            pan: tracks[track_id].pan,
+            rate: tracks[track_id].playback_rate,
+            // Fade in when crossfading between presets
+            fade_in: self.transition.map(|_| self.create_tween()),
+            loop_region: self.default_settings.loop_region.clone().unwrap_or(0.0..),
+        };
 
-        // Create streaming sound settings
-        let settings = StreamingSoundSettings::new()
-            .volume(clamped_volume)
-            .loop_region(self.default_settings.loop_region.clone().unwrap_or(0.0..));
+        self.ensure_tone_chain(track_id, tracks);
+        let manager = self
+            .manager
+            .as_mut()
+            .ok_or(AppError::Audio(AudioError::InitializationFailed))?;
+        let output = match self.tone_chains.get_mut(&track_id) {
+            Some(chain) => Output::Track(&mut chain.track),
+            None => Output::Main(manager),
+        };
+        let voice = tracks[track_id].source.start(output, start)?;
 
-        // Load and play the sound
-        let handle = self.load_and_play_sound(&track_path, settings)?;
+        // Stepping starts from now, not from whenever the last stepped track ended
+        if !self.needs_stepping() {
+            self.last_step = Some(Instant::now());
+        }
 
         // Store the handle and update track state
-        self.playing_handles.insert(track_id, handle);
+        self.playing_handles.insert(track_id, voice);
+        self.start_order.push(track_id);
         tracks[track_id].state = PlaybackState::Playing;
 
         log::info!("Started playing track: {track_name}");
@@ -184,28 +553,118 @@ impl AudioSystem {
         Ok(())
     }
 
-    // Load and play a sound file
-    fn load_and_play_sound(
-        &mut self,
-        path: &Path,
-        settings: StreamingSoundSettings,
-    ) -> Result<StreamingSoundHandle<FromFileError>, AppError> {
-        let sound_data =
-            StreamingSoundData::from_file(path).map_err(|e| AppError::Audio(e.into()))?;
+    // Apply the concurrency policy before another stream is opened
+    fn make_room(&mut self, tracks: &mut [NoiseTrack]) -> Result<(), AppError> {
+        let max = self.default_settings.max_concurrent_tracks;
+        self.start_order
+            .retain(|track_id| self.playing_handles.contains_key(track_id));
 
-        let manager = self
-            .manager
-            .as_mut()
-            .ok_or(AppError::Audio(AudioError::InitializationFailed))?;
+        while self.playing_handles.len() >= max {
+            match self.default_settings.concurrency_policy {
+                ConcurrencyPolicy::Refuse => {
+                    return Err(AppError::Audio(AudioError::TooManyTracks(max)));
+                }
+                ConcurrencyPolicy::StopOldest => {
+                    let Some(oldest) = self.start_order.first().copied() else {
+                        break;
+                    };
+                    log::info!("Track limit reached, stopping {}", tracks[oldest].name);
+                    self.stop_track(oldest, tracks)?;
+                    self.start_order.retain(|track_id| *track_id != oldest);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Create the track's sub-track with its filter and EQ effects, once
+    fn ensure_tone_chain(&mut self, track_id: usize, tracks: &[NoiseTrack]) {
+        let Some(manager) = &mut self.manager else {
+            return;
+        };
+        if self.tone_chains.contains_key(&track_id) {
+            return;
+        }
+
+        match ToneChain::new(manager, &tracks[track_id].tone) {
+            Ok(chain) => {
+                self.tone_chains.insert(track_id, chain);
+                if let Err(e) = self.apply_drift(track_id, tracks) {
+                    log::warn!("Playing {} without drift: {e}", tracks[track_id].name);
+                }
+            }
+            // Without a sub-track the sound still plays, just without tone shaping
+            Err(e) => log::warn!("Playing {} without filters: {e}", tracks[track_id].name),
+        }
+    }
+
+    // Start, retune or remove the volume drift of a track's tone chain. A drift that
+    // fails to start leaves the running one in place
+    fn apply_drift(&mut self, track_id: usize, tracks: &[NoiseTrack]) -> Result<(), AppError> {
+        let settings = tracks[track_id].drift;
+        let tween = self.create_tween();
+        let (Some(manager), Some(chain)) = (&mut self.manager, self.tone_chains.get_mut(&track_id))
+        else {
+            return Ok(());
+        };
+
+        if settings.is_off() {
+            if self.drifts.remove(&track_id).is_some() {
+                drift::detach(&mut chain.track, tween);
+            }
+            return Ok(());
+        }
 
-        let handle = manager
-            .play(sound_data.with_settings(settings))
-            .map_err(|e| {
-                log::error!("Failed to play sound: {e}");
-                AppError::Audio(AudioError::HandleCreationFailed)
-            })?;
+        match self.drifts.get_mut(&track_id) {
+            // Depth and rate retune the running modulator, only a new shape needs another one
+            Some(drift) if drift.settings().shape == settings.shape => {
+                if drift.settings().rate != settings.rate {
+                    drift.set_rate(settings.rate, tween);
+                }
+                drift.set_depth(settings.depth);
+                drift.attach(&mut chain.track, tween);
+            }
+            _ => {
+                let drift = Drift::new(manager, settings)?;
+                drift.attach(&mut chain.track, tween);
+                self.drifts.insert(track_id, drift);
+            }
+        }
+        Ok(())
+    }
+
+    // Whether an event, variation or drifting track is playing or paused, and needs `step`
+    pub fn needs_stepping(&self) -> bool {
+        self.playing_handles
+            .iter()
+            .any(|(track_id, voice)| voice.needs_stepping() || self.drifts.contains_key(track_id))
+    }
+
+    // Fire the event clips, start the variations and move the random drifts that have
+    // come due since the last step
+    pub fn step(&mut self, now: Instant) {
+        let elapsed = self
+            .last_step
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        self.last_step = self.needs_stepping().then_some(now);
+
+        for (track_id, drift) in &mut self.drifts {
+            if self.playing_handles.contains_key(track_id) {
+                drift.advance(elapsed);
+            }
+        }
 
-        Ok(handle)
+        let Some(manager) = &mut self.manager else {
+            return;
+        };
+        for (track_id, voice) in &mut self.playing_handles {
+            let output = match self.tone_chains.get_mut(track_id) {
+                Some(chain) => Output::Track(&mut chain.track),
+                None => Output::Main(&mut *manager),
+            };
+            voice.advance(elapsed, output);
+        }
     }
 
     // Pause a track by index
@@ -219,7 +678,7 @@ impl AudioSystem {
             }
         }
 
-        self.update_global_state();
+        self.update_global_state(tracks);
         Ok(())
     }
 
@@ -234,7 +693,7 @@ impl AudioSystem {
             }
         }
 
-        self.update_global_state();
+        self.update_global_state(tracks);
         Ok(())
     }
 
@@ -247,7 +706,7 @@ impl AudioSystem {
             log::info!("Stopped track: {}", tracks[track_id].name);
         }
 
-        self.update_global_state();
+        self.update_global_state(tracks);
         Ok(())
     }
 
@@ -259,26 +718,129 @@ impl AudioSystem {
         tracks: &mut [NoiseTrack],
     ) -> Result<(), AppError> {
         let tween = self.create_tween();
+        tracks[track_id].volume_level = volume;
+        let effective_volume = self.effective_volume(track_id, tracks);
         if let Some(handle) = self.playing_handles.get_mut(&track_id) {
-            // Calculate effective volume: combine track volume with master volume
-            let effective_volume = volume + self.default_settings.master_volume;
-            // Clamp to valid range
-            let clamped_volume = effective_volume.clamp(-60.0, 0.0);
-            handle.set_volume(clamped_volume, tween);
-            tracks[track_id].volume_level = volume;
+            handle.set_volume(effective_volume, tween);
             log::info!(
                 "Set volume to {} for track: {}",
                 volume,
                 tracks[track_id].name
             );
-        } else {
-            // Update track volume even if not playing
-            tracks[track_id].volume_level = volume;
         }
 
         Ok(())
     }
 
+    // Set the stereo position for a specific track
+    fn set_track_pan(
+        &mut self,
+        track_id: usize,
+        pan: f32,
+        tracks: &mut [NoiseTrack],
+    ) -> Result<(), AppError> {
+        let pan = pan.clamp(-1.0, 1.0);
+        let tween = self.create_tween();
+        tracks[track_id].pan = pan;
+        if let Some(handle) = self.playing_handles.get_mut(&track_id) {
+            handle.set_panning(pan, tween);
+            log::info!("Set pan to {pan} for track: {}", tracks[track_id].name);
+        }
+
+        Ok(())
+    }
+
+    // Set the playback speed for a specific track
+    fn set_track_playback_rate(
+        &mut self,
+        track_id: usize,
+        rate: f32,
+        tracks: &mut [NoiseTrack],
+    ) -> Result<(), AppError> {
+        let rate = rate.clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE);
+        let tween = self.create_tween();
+        tracks[track_id].playback_rate = rate;
+        if let Some(handle) = self.playing_handles.get_mut(&track_id) {
+            handle.set_playback_rate(rate, tween);
+            log::info!(
+                "Set playback rate to {rate} for track: {}",
+                tracks[track_id].name
+            );
+        }
+
+        Ok(())
+    }
+
+    // Set the filter and EQ settings for a specific track
+    fn set_track_tone(
+        &mut self,
+        track_id: usize,
+        tone: ToneSettings,
+        tracks: &mut [NoiseTrack],
+    ) -> Result<(), AppError> {
+        let tween = self.create_tween();
+        tracks[track_id].tone = tone;
+        if let Some(chain) = self.tone_chains.get_mut(&track_id) {
+            chain.apply(&tone, tween);
+        }
+
+        Ok(())
+    }
+
+    // Switch a track between its plain loop and a granular texture, or retune the texture
+    fn set_track_granular(
+        &mut self,
+        track_id: usize,
+        granular: Option<GranularSettings>,
+        tracks: &mut [NoiseTrack],
+    ) -> Result<(), AppError> {
+        let source = tracks[track_id].source.clone().with_granular(granular);
+        let switched = source.granular().is_some() != tracks[track_id].source.granular().is_some();
+        tracks[track_id].source = source;
+
+        let voice = self.playing_handles.get_mut(&track_id);
+        if let (Some(Voice::Granular(handle)), Some(granular)) = (voice, granular) {
+            handle.set_settings(granular);
+            return Ok(());
+        }
+        if !switched {
+            return Ok(());
+        }
+
+        // The loop and the texture are different sounds, one replaces the other
+        if let Some(mut voice) = self.playing_handles.remove(&track_id) {
+            voice.stop(self.create_tween());
+            if matches!(tracks[track_id].state, PlaybackState::Playing) {
+                self.start_new_track(track_id, tracks)?;
+            } else {
+                tracks[track_id].state = PlaybackState::Stopped;
+            }
+            self.update_global_state(tracks);
+        }
+
+        Ok(())
+    }
+
+    fn effective_volume(&self, track_id: usize, tracks: &[NoiseTrack]) -> f32 {
+        effective_volume(track_id, tracks, &self.default_settings)
+    }
+
+    // Re-apply the effective volume to every track with a handle
+    fn apply_volumes(&mut self, tracks: &[NoiseTrack]) {
+        let tween = self.create_tween();
+        let volumes: Vec<(usize, f32)> = self
+            .playing_handles
+            .keys()
+            .map(|track_id| (*track_id, self.effective_volume(*track_id, tracks)))
+            .collect();
+
+        for (track_id, volume) in volumes {
+            if let Some(handle) = self.playing_handles.get_mut(&track_id) {
+                handle.set_volume(volume, tween);
+            }
+        }
+    }
+
     // Stop all playing tracks
     fn stop_all_tracks(&mut self, tracks: &mut [NoiseTrack]) -> Result<(), AppError> {
         let track_ids: Vec<usize> = self.playing_handles.keys().copied().collect();
@@ -292,6 +854,27 @@ impl AudioSystem {
         Ok(())
     }
 
+    // Stop all tracks, fading out over the given duration
+    fn fade_out_all_tracks(
+        &mut self,
+        fade: Duration,
+        tracks: &mut [NoiseTrack],
+    ) -> Result<(), AppError> {
+        let tween = Tween {
+            duration: fade,
+            ..self.create_tween()
+        };
+
+        for (track_id, mut handle) in self.playing_handles.drain() {
+            handle.stop(tween);
+            tracks[track_id].state = PlaybackState::Stopped;
+        }
+
+        self.global_state = PlaybackState::Stopped;
+        log::info!("Fading out all tracks over {fade:?}");
+        Ok(())
+    }
+
     // Pause all playing tracks
     fn pause_all_tracks(&mut self, tracks: &mut [NoiseTrack]) -> Result<(), AppError> {
         let track_ids: Vec<usize> = self.playing_handles.keys().copied().collect();
@@ -302,7 +885,7 @@ impl AudioSystem {
             }
         }
 
-        self.update_global_state();
+        self.update_global_state(tracks);
         log::info!("Paused all tracks");
         Ok(())
     }
@@ -317,13 +900,14 @@ impl AudioSystem {
             }
         }
 
-        self.update_global_state();
+        self.update_global_state(tracks);
         log::info!("Resumed all tracks");
         Ok(())
     }
 
-    // Update the global playback state based on individual track states
-    fn update_global_state(&mut self) {
+    // Update the global playback state based on individual track states.
+    // Uses the requested track states since handle states lag behind while a tween runs.
+    fn update_global_state(&mut self, tracks: &[NoiseTrack]) {
         if self.playing_handles.is_empty() {
             self.global_state = PlaybackState::Stopped;
             return;
@@ -332,8 +916,8 @@ impl AudioSystem {
         let mut has_playing = false;
         let mut has_paused = false;
 
-        for handle in self.playing_handles.values() {
-            match handle.state() {
+        for track_id in self.playing_handles.keys() {
+            match tracks[*track_id].state {
                 PlaybackState::Playing => has_playing = true,
                 PlaybackState::Paused => has_paused = true,
                 _ => {}
@@ -352,7 +936,9 @@ impl AudioSystem {
     // Create a tween for smooth audio transitions
     fn create_tween(&self) -> Tween {
         Tween {
-            duration: self.default_settings.fade_duration,
+            duration: self
+                .transition
+                .unwrap_or(self.default_settings.fade_duration),
             easing: kira::Easing::Linear,
             start_time: kira::StartTime::Immediate,
         }
@@ -363,9 +949,15 @@ impl Default for AudioSystem {
     fn default() -> Self {
         Self::new().unwrap_or_else(|_| Self {
             manager: None,
+            master_bus: None,
             playing_handles: HashMap::new(),
+            tone_chains: HashMap::new(),
+            drifts: HashMap::new(),
+            start_order: vec![],
             global_state: PlaybackState::Stopped,
             default_settings: AudioSettings::default(),
+            transition: None,
+            last_step: None,
         })
     }
 }
@@ -374,13 +966,34 @@ impl std::fmt::Debug for AudioSystem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AudioSystem")
             .field("manager", &self.manager.is_some())
+            .field("master_bus", &self.master_bus.is_some())
             .field("playing_handles", &self.playing_handles.len())
+            .field("tone_chains", &self.tone_chains.len())
             .field("global_state", &self.global_state)
             .field("default_settings", &self.default_settings)
             .finish()
     }
 }
 
+// Volume a track is actually played at: track volume plus master volume,
+// or silence when the track is muted or another track is soloed
+pub fn effective_volume(track_id: usize, tracks: &[NoiseTrack], settings: &AudioSettings) -> f32 {
+    let track = &tracks[track_id];
+    let any_solo = tracks.iter().any(|track| track.solo);
+    if track.muted || (any_solo && !track.solo) {
+        return MIN_VOLUME_DB;
+    }
+
+    // Bring quiet and loud recordings to the same level first
+    let offset = match track.loudness {
+        Some(loudness) if settings.normalize_audio => loudness::gain_offset(loudness),
+        _ => 0.0,
+    };
+
+    // In dB, we add the values: track_volume + master_volume
+    (track.volume_level + offset + settings.master_volume).clamp(MIN_VOLUME_DB, MAX_VOLUME_DB)
+}
+
 // Convert decibel to % (0-100)
 // -60 dB = 0%, 0 dB = 100%
 pub fn db_to_percentage(db: f32) -> f32 {
@@ -399,10 +1012,31 @@ pub fn percentage_to_db(percentage: f32) -> f32 {
     (clamped_percentage / 100.0) * 60.0 - 60.0
 }
 
+// Convert a filter cutoff to a slider position (0.0 - 1.0), logarithmic like our hearing
+pub fn cutoff_to_position(cutoff: f32) -> f32 {
+    let cutoff = cutoff.clamp(MIN_CUTOFF_HZ, MAX_CUTOFF_HZ);
+    (cutoff / MIN_CUTOFF_HZ).ln() / (MAX_CUTOFF_HZ / MIN_CUTOFF_HZ).ln()
+}
+
+// Convert a slider position (0.0 - 1.0) to a filter cutoff in Hz
+pub fn position_to_cutoff(position: f32) -> f32 {
+    MIN_CUTOFF_HZ * (MAX_CUTOFF_HZ / MIN_CUTOFF_HZ).powf(position.clamp(0.0, 1.0))
+}
+
+// Convert a playback rate to semitones, 0.0 is the recorded pitch
+pub fn rate_to_semitones(rate: f32) -> f32 {
+    12.0 * rate.clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE).log2()
+}
+
+// Convert semitones to a playback rate
+pub fn semitones_to_rate(semitones: f32) -> f32 {
+    2f32.powf(semitones / 12.0)
+        .clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Duration;
 
     #[test]
     fn test_audio_system_creation() {
@@ -429,6 +1063,85 @@ mod tests {
         assert!(settings.loop_region.is_some());
     }
 
+    #[test]
+    fn test_cutoff_conversion() {
+        assert_eq!(position_to_cutoff(0.0), MIN_CUTOFF_HZ);
+        assert!((position_to_cutoff(1.0) - MAX_CUTOFF_HZ).abs() < 0.5);
+        assert!((cutoff_to_position(position_to_cutoff(0.5)) - 0.5).abs() < 1e-4);
+
+        // Clamping
+        assert_eq!(cutoff_to_position(1.0), 0.0);
+        assert_eq!(cutoff_to_position(50_000.0), 1.0);
+    }
+
+    #[test]
+    fn test_rate_conversion() {
+        assert_eq!(semitones_to_rate(0.0), 1.0);
+        assert_eq!(semitones_to_rate(-12.0), MIN_PLAYBACK_RATE);
+        assert!((rate_to_semitones(semitones_to_rate(7.0)) - 7.0).abs() < 1e-4);
+
+        // Clamping
+        assert_eq!(rate_to_semitones(8.0), 12.0);
+        assert_eq!(semitones_to_rate(-24.0), MIN_PLAYBACK_RATE);
+    }
+
+    #[test]
+    fn test_mute_and_solo() {
+        let mut audio_system = AudioSystem::default();
+        audio_system.set_master_volume(-10.0);
+        let mut tracks: Vec<NoiseTrack> = ["rain", "waves"]
+            .iter()
+            .map(|name| NoiseTrack::new(name.to_string(), name.into()))
+            .collect();
+
+        assert_eq!(audio_system.effective_volume(0, &tracks), -40.0);
+
+        audio_system
+            .process_command(AudioCommand::ToggleMute(0), &mut tracks)
+            .unwrap();
+        assert_eq!(audio_system.effective_volume(0, &tracks), MIN_VOLUME_DB);
+        // Muting keeps the track volume
+        assert_eq!(tracks[0].volume_level, crate::models::DEFAULT_VOLUME_DB);
+
+        audio_system
+            .process_command(AudioCommand::ToggleMute(0), &mut tracks)
+            .unwrap();
+        audio_system
+            .process_command(AudioCommand::ToggleSolo(1), &mut tracks)
+            .unwrap();
+        assert_eq!(audio_system.effective_volume(0, &tracks), MIN_VOLUME_DB);
+        assert_eq!(audio_system.effective_volume(1, &tracks), -40.0);
+    }
+
+    #[test]
+    fn test_normalization_offset() {
+        let mut audio_system = AudioSystem::default();
+        audio_system.set_master_volume(0.0);
+        let mut tracks = vec![NoiseTrack::new("storm".to_string(), "storm".into())];
+
+        audio_system
+            .process_command(AudioCommand::SetLoudness(vec![(0, -14.0)]), &mut tracks)
+            .unwrap();
+        // Measured but not applied until normalization is on
+        assert_eq!(audio_system.effective_volume(0, &tracks), -30.0);
+
+        audio_system.default_settings.normalize_audio = true;
+        assert_eq!(audio_system.effective_volume(0, &tracks), -36.0);
+    }
+
+    #[test]
+    fn test_refuse_over_limit() {
+        let mut audio_system = AudioSystem::default();
+        audio_system.default_settings.max_concurrent_tracks = 0;
+        let mut tracks = vec![NoiseTrack::new("rain".to_string(), "rain".into())];
+
+        assert_eq!(
+            audio_system.process_command(AudioCommand::Play(0), &mut tracks),
+            Err(AppError::Audio(AudioError::TooManyTracks(0)))
+        );
+        assert_eq!(tracks[0].state, PlaybackState::Stopped);
+    }
+
     #[test]
     fn test_volume_conversion() {
         // Test dB to percentage conversion
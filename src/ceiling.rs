@@ -0,0 +1,94 @@
+//! Output ceiling: the last stage of the master limiter.
+//!
+//! The limiter's compressor reacts in milliseconds, so the first transients of
+//! stacked loud tracks still get through it. The ceiling catches those: it
+//! passes samples below the knee unchanged and bends everything above it
+//! smoothly towards 0 dBFS, which the output then never exceeds.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use kira::Frame;
+use kira::effect::{Effect, EffectBuilder};
+use kira::info::Info;
+
+// Level where the curve starts to bend, about -1.9 dBFS
+const KNEE: f32 = 0.8;
+
+// A sample's level after the ceiling, linear up to the knee and approaching 1.0 above it
+pub fn ceiling(sample: f32) -> f32 {
+    let level = sample.abs();
+    if level <= KNEE {
+        return sample;
+    }
+
+    let headroom = 1.0 - KNEE;
+    let bent = KNEE + headroom * ((level - KNEE) / headroom).tanh();
+    bent.copysign(sample)
+}
+
+pub struct CeilingBuilder {
+    pub enabled: bool,
+}
+
+impl EffectBuilder for CeilingBuilder {
+    type Handle = CeilingHandle;
+
+    fn build(self) -> (Box<dyn Effect>, CeilingHandle) {
+        let enabled = Arc::new(AtomicBool::new(self.enabled));
+        let effect = Ceiling {
+            enabled: enabled.clone(),
+        };
+        (Box::new(effect), CeilingHandle { enabled })
+    }
+}
+
+// Turns the ceiling on and off while the mix plays. It only touches samples above the
+// knee, so switching needs no fade
+pub struct CeilingHandle {
+    enabled: Arc<AtomicBool>,
+}
+
+impl CeilingHandle {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+}
+
+struct Ceiling {
+    enabled: Arc<AtomicBool>,
+}
+
+impl Effect for Ceiling {
+    fn process(&mut self, input: &mut [Frame], _dt: f64, _info: &Info) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        for frame in input {
+            frame.left = ceiling(frame.left);
+            frame.right = ceiling(frame.right);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ceiling() {
+        // Untouched below the knee
+        assert_eq!(ceiling(0.5), 0.5);
+        assert_eq!(ceiling(-KNEE), -KNEE);
+
+        // Still rising above it, but never past full scale
+        let mut previous = KNEE;
+        for level in [0.9, 1.0, 1.2] {
+            let limited = ceiling(level);
+            assert!(limited > previous && limited < 1.0);
+            assert_eq!(ceiling(-level), -limited);
+            previous = limited;
+        }
+        assert!(ceiling(4.0) <= 1.0);
+    }
+}
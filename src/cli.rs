@@ -0,0 +1,137 @@
+use crate::ipc::Request;
+use crate::pcm::PcmOutput;
+
+pub const USAGE: &str = "usage: cosmic_noise [--headless] [--pcm-out PATH] [command]
+
+options:
+  --headless            run without a window, controlled over the socket or D-Bus
+  --pcm-out PATH        write the mix as raw stereo PCM to PATH (a file or FIFO, - for stdout)
+                        instead of the sound card
+  --pcm-rate HZ         sample rate of the PCM output (default 48000)
+  --pcm-format FORMAT   s16le (default) or f32le
+  --pcm-tee             keep playing on the sound card too, at its own sample rate
+
+commands are forwarded to the running instance, see README for the list";
+
+// Parsed command line
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Options {
+    // Run the audio engine without opening a window
+    pub headless: bool,
+    // Control command to run or forward to the running instance
+    pub request: Option<Request>,
+    // Raw PCM output of the mix
+    pub pcm_output: Option<PcmOutput>,
+}
+
+impl Options {
+    // Parse the arguments following the program name
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = Options::default();
+        let mut words = Vec::new();
+        let mut pcm_rate = None;
+        let mut pcm_format = None;
+        let mut pcm_tee = false;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--pcm-out" => {
+                    options.pcm_output = Some(PcmOutput::new(value(&mut args, &arg)?));
+                }
+                "--pcm-rate" => {
+                    let rate = value(&mut args, &arg)?;
+                    match rate.parse() {
+                        Ok(rate) if rate > 0 => pcm_rate = Some(rate),
+                        _ => return Err(format!("invalid sample rate '{rate}'")),
+                    }
+                }
+                "--pcm-format" => pcm_format = Some(value(&mut args, &arg)?.parse()?),
+                "--pcm-tee" => pcm_tee = true,
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                flag if flag.starts_with("--") => {
+                    return Err(format!("unknown option '{flag}'\n{USAGE}"));
+                }
+                _ => words.push(arg),
+            }
+        }
+
+        if !words.is_empty() {
+            options.request = Some(Request::parse(&words.join(" "))?);
+        }
+
+        match &mut options.pcm_output {
+            Some(output) => {
+                output.sample_rate = pcm_rate.unwrap_or(output.sample_rate);
+                output.format = pcm_format.unwrap_or(output.format);
+                output.tee = pcm_tee;
+            }
+            None if pcm_rate.is_some() || pcm_format.is_some() || pcm_tee => {
+                return Err(format!("PCM options need --pcm-out\n{USAGE}"));
+            }
+            None => {}
+        }
+
+        Ok(options)
+    }
+}
+
+// Value following an option, e.g. the path after `--pcm-out`
+fn value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("{option} needs a value\n{USAGE}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcm::PcmFormat;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(parse(&[]), Ok(Options::default()));
+
+        let options = parse(&["--headless", "preset", "Evening rain"]).unwrap();
+        assert!(options.headless);
+        assert_eq!(
+            options.request,
+            Some(Request::ApplyPreset("Evening rain".to_string()))
+        );
+
+        assert!(parse(&["--loud"]).is_err());
+        assert!(parse(&["jump"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_pcm_options() {
+        let options = parse(&["--headless", "--pcm-out", "-"]).unwrap();
+        assert_eq!(options.pcm_output, Some(PcmOutput::new("-")));
+
+        let options = parse(&[
+            "--pcm-out",
+            "/tmp/noise.fifo",
+            "--pcm-rate",
+            "44100",
+            "--pcm-format",
+            "f32le",
+            "--pcm-tee",
+        ])
+        .unwrap();
+        let output = options.pcm_output.unwrap();
+        assert_eq!(output.sample_rate, 44100);
+        assert_eq!(output.format, PcmFormat::F32Le);
+        assert!(output.tee);
+
+        assert!(parse(&["--pcm-out"]).is_err());
+        assert!(parse(&["--pcm-out", "-", "--pcm-rate", "fast"]).is_err());
+        assert!(parse(&["--pcm-format", "f32le"]).is_err());
+    }
+}
@@ -1,6 +1,13 @@
 use crate::errors::{AppError, ConfigError};
-use crate::models::{AppConfig, AppTheme};
+use crate::models::{
+    Alarm, AppConfig, AppTheme, AudioSettings, ConcurrencyPolicy, FocusSettings, GeneratorSettings,
+    KeymapSettings, LimiterSettings, Preset, Schedule,
+};
 use log::{error, info, warn};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::path::Path;
 
 // Application information for confy
 const APP_NAME: &str = "cosmic-noise";
@@ -41,6 +48,31 @@ impl ConfigManager {
         Ok(())
     }
 
+    // Load a TOML file kept next to the configuration, e.g. caches and history.
+    // Missing or unreadable files give the default
+    pub fn load_toml<T: DeserializeOwned + Default>(path: &Path) -> T {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    // Save a TOML file kept next to the configuration, creating its directory
+    pub fn save_toml<T: Serialize>(path: &Path, value: &T) -> Result<(), AppError> {
+        toml::to_string(value)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                std::fs::write(path, contents).map_err(|e| e.to_string())
+            })
+            .map_err(|e| {
+                error!("Failed to save {}: {e}", path.display());
+                AppError::Config(ConfigError::SaveFailed)
+            })
+    }
+
     // Load only the theme from configuration
     pub fn load_theme() -> AppTheme {
         match Self::load() {
@@ -76,6 +108,241 @@ impl ConfigManager {
         config.audio.master_volume = volume;
         Self::save(&config)
     }
+
+    // Load only the master bus limiter settings from configuration
+    pub fn load_limiter() -> LimiterSettings {
+        match Self::load() {
+            Ok(config) => config.audio.limiter,
+            Err(e) => {
+                warn!("Failed to load limiter settings from configuration: {e}");
+                LimiterSettings::default()
+            }
+        }
+    }
+
+    // Save only the master bus limiter settings to configuration
+    pub fn save_limiter(limiter: LimiterSettings) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.audio.limiter = limiter;
+        Self::save(&config)
+    }
+
+    // Load only the loudness normalization preference from configuration
+    pub fn load_normalize_audio() -> bool {
+        match Self::load() {
+            Ok(config) => config.audio.normalize_audio,
+            Err(e) => {
+                warn!("Failed to load normalization setting from configuration: {e}");
+                false
+            }
+        }
+    }
+
+    // Save only the loudness normalization preference to configuration
+    pub fn save_normalize_audio(enabled: bool) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.audio.normalize_audio = enabled;
+        Self::save(&config)
+    }
+
+    // Load only the concurrent track limit and policy from configuration
+    pub fn load_concurrency() -> (usize, ConcurrencyPolicy) {
+        match Self::load() {
+            Ok(config) => (
+                config.audio.max_concurrent_tracks,
+                config.audio.concurrency_policy,
+            ),
+            Err(e) => {
+                warn!("Failed to load track limit from configuration: {e}");
+                let defaults = AudioSettings::default();
+                (defaults.max_concurrent_tracks, defaults.concurrency_policy)
+            }
+        }
+    }
+
+    // Save only the concurrency policy to configuration
+    pub fn save_concurrency_policy(policy: ConcurrencyPolicy) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.audio.concurrency_policy = policy;
+        Self::save(&config)
+    }
+
+    // Load only the close-to-tray preference from configuration
+    pub fn load_close_to_tray() -> bool {
+        match Self::load() {
+            Ok(config) => config.ui.close_to_tray,
+            Err(e) => {
+                warn!("Failed to load close-to-tray setting from configuration: {e}");
+                false
+            }
+        }
+    }
+
+    // Save only the close-to-tray preference to configuration
+    pub fn save_close_to_tray(enabled: bool) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.ui.close_to_tray = enabled;
+        Self::save(&config)
+    }
+
+    // Load only the favorite track names from configuration
+    pub fn load_favorites() -> Vec<String> {
+        match Self::load() {
+            Ok(config) => config.ui.favorites,
+            Err(e) => {
+                warn!("Failed to load favorites from configuration: {e}");
+                vec![]
+            }
+        }
+    }
+
+    // Save only the favorite track names to configuration
+    pub fn save_favorites(favorites: &[String]) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.ui.favorites = favorites.to_vec();
+        Self::save(&config)
+    }
+
+    // Load only the collapsed section names from configuration
+    pub fn load_collapsed_sections() -> Vec<String> {
+        match Self::load() {
+            Ok(config) => config.ui.collapsed_sections,
+            Err(e) => {
+                warn!("Failed to load collapsed sections from configuration: {e}");
+                vec![]
+            }
+        }
+    }
+
+    // Save only the collapsed section names to configuration
+    pub fn save_collapsed_sections(sections: &[String]) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.ui.collapsed_sections = sections.to_vec();
+        Self::save(&config)
+    }
+
+    // Load only the keyboard shortcuts from configuration
+    pub fn load_keymap() -> KeymapSettings {
+        match Self::load() {
+            Ok(config) => config.keymap,
+            Err(e) => {
+                warn!("Failed to load keymap from configuration: {e}");
+                KeymapSettings::default()
+            }
+        }
+    }
+
+    // Load only the saved presets from configuration
+    pub fn load_presets() -> Vec<Preset> {
+        match Self::load() {
+            Ok(config) => config.presets,
+            Err(e) => {
+                warn!("Failed to load presets from configuration: {e}");
+                vec![]
+            }
+        }
+    }
+
+    // Save only the presets to configuration
+    pub fn save_presets(presets: &[Preset]) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.presets = presets.to_vec();
+        Self::save(&config)
+    }
+
+    // Load only the wake-up alarms from configuration
+    pub fn load_alarms() -> Vec<Alarm> {
+        match Self::load() {
+            Ok(config) => config.alarms,
+            Err(e) => {
+                warn!("Failed to load alarms from configuration: {e}");
+                vec![]
+            }
+        }
+    }
+
+    // Save only the wake-up alarms to configuration
+    pub fn save_alarms(alarms: &[Alarm]) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.alarms = alarms.to_vec();
+        Self::save(&config)
+    }
+
+    // Load only the preset schedule from configuration
+    pub fn load_schedule() -> Schedule {
+        match Self::load() {
+            Ok(config) => config.schedule,
+            Err(e) => {
+                warn!("Failed to load schedule from configuration: {e}");
+                Schedule::default()
+            }
+        }
+    }
+
+    // Save only the preset schedule to configuration
+    pub fn save_schedule(schedule: &Schedule) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.schedule = schedule.clone();
+        Self::save(&config)
+    }
+
+    // Load only the focus session settings from configuration
+    pub fn load_focus() -> FocusSettings {
+        match Self::load() {
+            Ok(config) => config.focus,
+            Err(e) => {
+                warn!("Failed to load focus settings from configuration: {e}");
+                FocusSettings::default()
+            }
+        }
+    }
+
+    // Save only the focus session settings to configuration
+    pub fn save_focus(focus: &FocusSettings) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.focus = focus.clone();
+        Self::save(&config)
+    }
+
+    // Load only the generator track settings from configuration
+    pub fn load_generator() -> GeneratorSettings {
+        match Self::load() {
+            Ok(config) => config.generator,
+            Err(e) => {
+                warn!("Failed to load generator settings from configuration: {e}");
+                GeneratorSettings::default()
+            }
+        }
+    }
+
+    // Save only the generator track settings to configuration
+    pub fn save_generator(generator: GeneratorSettings) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        config.generator = generator;
+        Self::save(&config)
+    }
+
+    // Load only the track stereo positions from configuration
+    pub fn load_pans() -> BTreeMap<String, f32> {
+        match Self::load() {
+            Ok(config) => config.pans,
+            Err(e) => {
+                warn!("Failed to load track pans from configuration: {e}");
+                BTreeMap::new()
+            }
+        }
+    }
+
+    // Save only the stereo position of one track to configuration
+    pub fn save_pan(track: &str, pan: f32) -> Result<(), AppError> {
+        let mut config = Self::load().unwrap_or_default();
+        if pan == 0.0 {
+            config.pans.remove(track);
+        } else {
+            config.pans.insert(track.to_string(), pan);
+        }
+        Self::save(&config)
+    }
 }
 
 #[cfg(test)]
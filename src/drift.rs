@@ -0,0 +1,158 @@
+//! Volume drift: a slow swell of a track's level around its set volume.
+//!
+//! The drift is a kira modulator driving the volume of the track's sub-track
+//! between `-depth` and `+depth` dB. The wave shape is a plain LFO. The random
+//! shape is a tweener sent to a new random target every half swell, which
+//! gives smoothed noise: gusts of different strength that never line up.
+
+use std::time::Duration;
+
+use kira::backend::Backend;
+use kira::modulator::lfo::{LfoBuilder, LfoHandle, Waveform};
+use kira::modulator::tweener::{TweenerBuilder, TweenerHandle};
+use kira::track::TrackHandle;
+use kira::{AudioManager, Decibels, Easing, Mapping, StartTime, Tween, Value};
+
+use crate::errors::{AppError, AudioError};
+use crate::models::{DriftSettings, DriftShape};
+
+enum Modulator {
+    Wave(LfoHandle),
+    Random(TweenerHandle),
+}
+
+// Running drift of one track
+pub struct Drift {
+    settings: DriftSettings,
+    modulator: Modulator,
+    // Time left until the random shape picks its next target
+    countdown: Duration,
+    rng: fastrand::Rng,
+}
+
+impl Drift {
+    pub fn new<B: Backend>(
+        manager: &mut AudioManager<B>,
+        settings: DriftSettings,
+    ) -> Result<Self, AppError> {
+        let modulator = match settings.shape {
+            DriftShape::Wave => manager
+                .add_modulator(
+                    LfoBuilder::new()
+                        .waveform(Waveform::Sine)
+                        .frequency(1.0 / settings.period().as_secs_f64()),
+                )
+                .map(Modulator::Wave),
+            DriftShape::Random => manager
+                .add_modulator(TweenerBuilder { initial_value: 0.0 })
+                .map(Modulator::Random),
+        }
+        .map_err(|e| {
+            log::error!("Failed to create volume drift: {e}");
+            AppError::Audio(AudioError::HandleCreationFailed)
+        })?;
+
+        Ok(Self {
+            settings,
+            modulator,
+            countdown: Duration::ZERO,
+            rng: fastrand::Rng::new(),
+        })
+    }
+
+    pub fn settings(&self) -> DriftSettings {
+        self.settings
+    }
+
+    // Takes effect on the next `attach`
+    pub fn set_depth(&mut self, depth: f32) {
+        self.settings.depth = depth;
+    }
+
+    // Retune the running modulator to a new swell period
+    pub fn set_rate(&mut self, rate: f32, tween: Tween) {
+        self.settings.rate = rate;
+        let swing = self.settings.period() / 2;
+        match &mut self.modulator {
+            Modulator::Wave(lfo) => {
+                lfo.set_frequency(1.0 / self.settings.period().as_secs_f64(), tween)
+            }
+            // The swell under way finishes, a slower rate doesn't wait for it
+            Modulator::Random(_) => self.countdown = self.countdown.min(swing),
+        }
+    }
+
+    // Volume of the sub-track following the modulator
+    fn volume(&self) -> Value<Decibels> {
+        let id = match &self.modulator {
+            Modulator::Wave(lfo) => lfo.id(),
+            Modulator::Random(tweener) => tweener.id(),
+        };
+        Value::FromModulator {
+            id,
+            mapping: Mapping {
+                input_range: (-1.0, 1.0),
+                output_range: (
+                    Decibels(-self.settings.depth),
+                    Decibels(self.settings.depth),
+                ),
+                easing: Easing::Linear,
+            },
+        }
+    }
+
+    // Let the sub-track volume follow the drift
+    pub fn attach(&self, track: &mut TrackHandle, tween: Tween) {
+        track.set_volume(self.volume(), tween);
+    }
+
+    // Let `elapsed` pass, moving the random shape towards a new target when due
+    pub fn advance(&mut self, elapsed: Duration) {
+        let Modulator::Random(tweener) = &mut self.modulator else {
+            return;
+        };
+        self.countdown = self.countdown.saturating_sub(elapsed);
+        if !self.countdown.is_zero() {
+            return;
+        }
+
+        let swing = self.settings.period() / 2;
+        tweener.set(
+            random_target(self.rng.f64()),
+            Tween {
+                duration: swing,
+                easing: Easing::InOutPowi(2),
+                start_time: StartTime::Immediate,
+            },
+        );
+        self.countdown = swing;
+    }
+}
+
+// Target between -1.0 and 1.0 for a `roll` between 0.0 and 1.0, kept away from
+// the middle so every swell is noticeable
+fn random_target(roll: f64) -> f64 {
+    let swell = 0.3 + 0.7 * (2.0 * roll - 1.0).abs();
+    if roll < 0.5 { -swell } else { swell }
+}
+
+// Put a sub-track back at its set volume
+pub fn detach(track: &mut TrackHandle, tween: Tween) {
+    track.set_volume(Decibels::IDENTITY, tween);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_target() {
+        assert_eq!(random_target(0.0), -1.0);
+        assert_eq!(random_target(1.0), 1.0);
+        assert!((random_target(0.5) - 0.3).abs() < 1e-9);
+        for roll in [0.1, 0.3, 0.49, 0.7, 0.9] {
+            let target = random_target(roll);
+            assert!((0.3..=1.0).contains(&target.abs()));
+        }
+    }
+}
@@ -45,6 +45,10 @@ pub enum AudioError {
     DecoderError(String),
     // Playback error during runtime
     PlaybackError(String),
+    // The concurrent track limit was reached
+    TooManyTracks(usize),
+    // Rendering the mix to a file failed
+    ExportFailed(String),
 }
 
 // Configuration related errors
@@ -52,6 +56,8 @@ pub enum AudioError {
 pub enum ConfigError {
     // Failed to save configuration
     SaveFailed,
+    // No preset with the given name exists
+    PresetNotFound(String),
 }
 
 impl fmt::Display for AppError {
@@ -116,6 +122,12 @@ impl fmt::Display for AudioError {
             AudioError::PlaybackError(msg) => {
                 write!(f, "Playback error: {msg}")
             }
+            AudioError::TooManyTracks(max) => {
+                write!(f, "Only {max} tracks can play at the same time")
+            }
+            AudioError::ExportFailed(msg) => {
+                write!(f, "Export failed: {msg}")
+            }
         }
     }
 }
@@ -126,6 +138,9 @@ impl fmt::Display for ConfigError {
             ConfigError::SaveFailed => {
                 write!(f, "Failed to save configuration")
             }
+            ConfigError::PresetNotFound(name) => {
+                write!(f, "No preset named '{name}'")
+            }
         }
     }
 }
@@ -0,0 +1,255 @@
+//! Event tracks: one-shot clips fired at random intervals.
+//!
+//! An event track is a folder of short clips (thunder, bird calls, a distant
+//! train) with an `event.toml` file. Instead of looping one recording, a clip
+//! is picked at random every so often and played at a slightly different
+//! level and stereo position, so the ambience never repeats the same way.
+
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use kira::sound::PlaybackState;
+use kira::sound::static_sound::{StaticSoundData, StaticSoundHandle, StaticSoundSettings};
+use kira::{Decibels, Panning, PlaybackRate, StartTime, Tween};
+
+use crate::errors::AppError;
+use crate::models::{EventSettings, MIN_VOLUME_DB};
+use crate::utils::files::folder_clips;
+
+// Shortest pause between two clips, whatever the rate and jitter
+const MIN_INTERVAL: Duration = Duration::from_millis(500);
+// Clips due this far ahead are handed to kira with a start delay, so they land on
+// time between the coarse steps. Twice the step period, a late step doesn't delay them
+const SCHEDULE_AHEAD: Duration = Duration::from_secs(2);
+
+// Time until the next clip for a random `roll` between 0.0 and 1.0
+pub fn next_interval(settings: &EventSettings, roll: f32) -> Duration {
+    let mean = 60.0 / settings.rate.max(0.01);
+    let jitter = settings.jitter.clamp(0.0, 1.0);
+    let factor = 1.0 + jitter * (2.0 * roll - 1.0);
+    Duration::from_secs_f32(mean * factor).max(MIN_INTERVAL)
+}
+
+// A clip that is scheduled or hasn't finished yet, with how far its level and
+// position differ from the track's
+struct Ringing {
+    handle: StaticSoundHandle,
+    offset: f32,
+    spread: f32,
+}
+
+// Clips of a playing event track and the ones still ringing
+pub struct EventLayer {
+    clips: Vec<StaticSoundData>,
+    settings: EventSettings,
+    // Time left until the next clip that isn't scheduled yet
+    countdown: Duration,
+    // Effective track volume in dB and pan the clips vary around
+    volume: f32,
+    pan: f32,
+    rate: f32,
+    paused: bool,
+    ringing: Vec<Ringing>,
+    rng: fastrand::Rng,
+}
+
+impl EventLayer {
+    // Decode every clip of the folder up front, they are short
+    pub fn load(
+        folder: &Path,
+        settings: EventSettings,
+        volume: f32,
+        pan: f32,
+        rate: f32,
+    ) -> Result<Self, AppError> {
+        let clips = folder_clips(folder)?
+            .iter()
+            .map(|path| StaticSoundData::from_file(path).map_err(|e| AppError::Audio(e.into())))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut rng = fastrand::Rng::new();
+        Ok(Self {
+            countdown: next_interval(&settings, rng.f32()),
+            clips,
+            settings,
+            volume,
+            pan,
+            rate,
+            paused: false,
+            ringing: vec![],
+            rng,
+        })
+    }
+
+    pub fn state(&self) -> PlaybackState {
+        if self.paused {
+            PlaybackState::Paused
+        } else {
+            PlaybackState::Playing
+        }
+    }
+
+    // Let `elapsed` pass and schedule the clips due within `SCHEDULE_AHEAD` through `play`
+    pub fn advance<E: fmt::Display>(
+        &mut self,
+        elapsed: Duration,
+        mut play: impl FnMut(StaticSoundData) -> Result<StaticSoundHandle, E>,
+    ) {
+        self.ringing
+            .retain(|clip| !matches!(clip.handle.state(), PlaybackState::Stopped));
+        if self.paused {
+            return;
+        }
+
+        self.countdown = self.countdown.saturating_sub(elapsed);
+        while self.countdown < SCHEDULE_AHEAD {
+            let delay = self.countdown;
+            self.countdown += next_interval(&self.settings, self.rng.f32());
+            // Silent tracks skip their clips instead of playing them inaudibly
+            if self.volume <= MIN_VOLUME_DB {
+                continue;
+            }
+
+            let clip = self.clips[self.rng.usize(..self.clips.len())].clone();
+            let offset = -self.rng.f32() * self.settings.volume_variance.max(0.0);
+            let spread = self.settings.pan_spread.clamp(0.0, 1.0) * (2.0 * self.rng.f32() - 1.0);
+            let settings = StaticSoundSettings::new()
+                .start_time(StartTime::Delayed(delay))
+                .volume(self.volume + offset)
+                .panning(Panning((self.pan + spread).clamp(-1.0, 1.0)))
+                .playback_rate(PlaybackRate(self.rate as f64));
+
+            match play(clip.with_settings(settings)) {
+                Ok(handle) => self.ringing.push(Ringing {
+                    handle,
+                    offset,
+                    spread,
+                }),
+                Err(e) => log::error!("Failed to play event clip: {e}"),
+            }
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32, tween: Tween) {
+        self.volume = volume;
+        for clip in &mut self.ringing {
+            clip.handle
+                .set_volume(Decibels(volume + clip.offset), tween);
+        }
+    }
+
+    pub fn set_panning(&mut self, pan: f32, tween: Tween) {
+        self.pan = pan;
+        for clip in &mut self.ringing {
+            let pan = (pan + clip.spread).clamp(-1.0, 1.0);
+            clip.handle.set_panning(Panning(pan), tween);
+        }
+    }
+
+    pub fn set_playback_rate(&mut self, rate: f32, tween: Tween) {
+        self.rate = rate;
+        for clip in &mut self.ringing {
+            clip.handle
+                .set_playback_rate(PlaybackRate(rate as f64), tween);
+        }
+    }
+
+    pub fn pause(&mut self, tween: Tween) {
+        self.paused = true;
+        for clip in &mut self.ringing {
+            clip.handle.pause(tween);
+        }
+    }
+
+    pub fn resume(&mut self, tween: Tween) {
+        self.paused = false;
+        for clip in &mut self.ringing {
+            clip.handle.resume(tween);
+        }
+    }
+
+    pub fn stop(&mut self, tween: Tween) {
+        for clip in &mut self.ringing {
+            clip.handle.stop(tween);
+        }
+        self.ringing.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::AudioError;
+
+    #[test]
+    fn test_next_interval() {
+        let settings = EventSettings {
+            rate: 2.0,
+            jitter: 0.5,
+            ..EventSettings::default()
+        };
+        assert_eq!(next_interval(&settings, 0.5), Duration::from_secs(30));
+        assert_eq!(next_interval(&settings, 0.0), Duration::from_secs(15));
+        assert_eq!(next_interval(&settings, 1.0), Duration::from_secs(45));
+
+        // Very high rates are capped
+        let busy = EventSettings {
+            rate: 1_000.0,
+            ..settings
+        };
+        assert_eq!(next_interval(&busy, 0.0), MIN_INTERVAL);
+    }
+
+    #[test]
+    fn test_clips_scheduled_ahead() {
+        // Two clips a second, exactly
+        let settings = EventSettings {
+            rate: 120.0,
+            jitter: 0.0,
+            ..EventSettings::default()
+        };
+        let mut layer = EventLayer {
+            clips: vec![StaticSoundData {
+                sample_rate: 48_000,
+                frames: vec![kira::Frame::ZERO; 16].into(),
+                settings: StaticSoundSettings::default(),
+                slice: None,
+            }],
+            settings,
+            countdown: Duration::from_millis(300),
+            volume: -10.0,
+            pan: 0.0,
+            rate: 1.0,
+            paused: false,
+            ringing: vec![],
+            rng: fastrand::Rng::with_seed(3),
+        };
+
+        let mut delays = Vec::new();
+        let mut play = |clip: StaticSoundData| {
+            if let StartTime::Delayed(delay) = clip.settings.start_time {
+                delays.push(delay.as_millis());
+            }
+            Err::<StaticSoundHandle, _>("no audio in tests")
+        };
+        layer.advance(Duration::ZERO, &mut play);
+        layer.advance(Duration::from_secs(1), &mut play);
+
+        // Clips keep their half second spacing across the one second steps
+        assert_eq!(delays, [300, 800, 1300, 1800, 1300, 1800]);
+    }
+
+    #[test]
+    fn test_load_empty_folder() {
+        let folder = std::env::temp_dir().join("cosmic-noise-empty-event");
+        std::fs::create_dir_all(&folder).unwrap();
+
+        let result = EventLayer::load(&folder, EventSettings::default(), -30.0, 0.0, 1.0);
+        assert!(matches!(
+            result,
+            Err(AppError::Audio(AudioError::PlaybackError(_)))
+        ));
+        let _ = std::fs::remove_dir(folder);
+    }
+}
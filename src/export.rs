@@ -0,0 +1,566 @@
+//! Offline rendering of the current mix to a WAV or FLAC file.
+//!
+//! The mix is rebuilt on a separate `AudioManager` whose backend never talks
+//! to a sound card: it only hands us kira's renderer, which is then driven as
+//! fast as the CPU allows. Tracks are decoded up front as static sounds since
+//! streaming decoders can't keep up with faster than real time rendering.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flacenc::bitsink::ByteSink;
+use flacenc::component::{BitRepr, Stream, StreamInfo};
+use flacenc::error::{Verified, Verify};
+use flacenc::source::{Fill, FrameBuf};
+use kira::backend::{Backend, Renderer};
+use kira::sound::PlaybackState;
+use kira::sound::static_sound::{StaticSoundData, StaticSoundHandle, StaticSoundSettings};
+use kira::track::{MainTrackBuilder, TrackHandle};
+use kira::{AudioManager, AudioManagerSettings, Panning, PlaybackRate, Tween};
+
+use crate::audio::{MasterBus, ToneChain, effective_volume};
+use crate::drift::Drift;
+use crate::errors::{AppError, AudioError};
+use crate::events::EventLayer;
+use crate::models::{AudioSettings, NoiseTrack, SoundSource, VariationSettings};
+use crate::source::{Output, Start};
+use crate::utils::files::folder_clips;
+use crate::variations::Rotation;
+
+pub const EXPORT_SAMPLE_RATE: u32 = 48_000;
+// Longest render offered in the settings
+pub const MAX_EXPORT_MINUTES: u64 = 180;
+// Fade applied at the start and the end when fading is enabled
+pub const EXPORT_FADE: Duration = Duration::from_secs(10);
+
+const CHANNELS: u16 = 2;
+const BITS_PER_SAMPLE: u16 = 16;
+// Frames rendered per call into kira
+const RENDER_BLOCK_FRAMES: usize = 1024;
+
+// File format of an exported mix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Wav,
+    Flac,
+}
+
+impl ExportFormat {
+    pub fn all() -> &'static [ExportFormat] {
+        &[ExportFormat::Wav, ExportFormat::Flac]
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Wav => "wav",
+            ExportFormat::Flac => "flac",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Wav => write!(f, "WAV"),
+            ExportFormat::Flac => write!(f, "FLAC"),
+        }
+    }
+}
+
+// What the user picked in the export form
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportOptions {
+    // Length of the rendered file
+    pub duration: Duration,
+    // Fade in at the start and out at the end
+    pub fade: bool,
+    pub format: ExportFormat,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(30 * 60),
+            fade: true,
+            format: ExportFormat::default(),
+        }
+    }
+}
+
+// Snapshot of the mix to render, taken when the export starts
+#[derive(Debug, Clone)]
+pub struct ExportJob {
+    pub tracks: Vec<NoiseTrack>,
+    pub settings: AudioSettings,
+    pub options: ExportOptions,
+    pub path: PathBuf,
+}
+
+impl ExportJob {
+    // Render the playing tracks into the user's music directory
+    pub fn new(tracks: &[NoiseTrack], settings: &AudioSettings, options: ExportOptions) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        let directory = dirs::audio_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(std::env::temp_dir);
+
+        Self {
+            tracks: tracks.to_vec(),
+            settings: settings.clone(),
+            options,
+            path: directory.join(format!(
+                "cosmic-noise-mix-{timestamp}.{}",
+                options.format.extension()
+            )),
+        }
+    }
+}
+
+// Backend without an output device, it only keeps the renderer for us
+struct OfflineBackend {
+    renderer: Option<Renderer>,
+}
+
+impl Backend for OfflineBackend {
+    // Sample rate to render at
+    type Settings = u32;
+    type Error = Infallible;
+
+    fn setup(sample_rate: u32, _internal_buffer_size: usize) -> Result<(Self, u32), Infallible> {
+        Ok((Self { renderer: None }, sample_rate))
+    }
+
+    fn start(&mut self, renderer: Renderer) -> Result<(), Infallible> {
+        self.renderer = Some(renderer);
+        Ok(())
+    }
+}
+
+fn export_error(message: impl fmt::Display) -> AppError {
+    AppError::Audio(AudioError::ExportFailed(message.to_string()))
+}
+
+// Gain of a frame with linear fades of `fade_frames` at both ends
+pub fn fade_gain(frame: usize, total_frames: usize, fade_frames: usize) -> f32 {
+    if fade_frames == 0 {
+        return 1.0;
+    }
+
+    let fade_in = frame as f32 / fade_frames as f32;
+    let fade_out = total_frames.saturating_sub(frame) as f32 / fade_frames as f32;
+    fade_in.min(fade_out).min(1.0)
+}
+
+fn flac_error(error: impl fmt::Debug) -> AppError {
+    export_error(format!("{error:?}"))
+}
+
+// Destination of the rendered samples
+enum SampleWriter {
+    Wav(hound::WavWriter<BufWriter<File>>),
+    Flac(FlacWriter),
+}
+
+impl SampleWriter {
+    fn create(path: &Path, format: ExportFormat) -> Result<Self, AppError> {
+        match format {
+            ExportFormat::Wav => {
+                let spec = hound::WavSpec {
+                    channels: CHANNELS,
+                    sample_rate: EXPORT_SAMPLE_RATE,
+                    bits_per_sample: BITS_PER_SAMPLE,
+                    sample_format: hound::SampleFormat::Int,
+                };
+                hound::WavWriter::create(path, spec)
+                    .map(SampleWriter::Wav)
+                    .map_err(export_error)
+            }
+            ExportFormat::Flac => FlacWriter::create(path).map(SampleWriter::Flac),
+        }
+    }
+
+    fn write(&mut self, sample: f32) -> Result<(), AppError> {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        match self {
+            SampleWriter::Wav(writer) => writer.write_sample(sample).map_err(export_error),
+            SampleWriter::Flac(writer) => writer.write(sample),
+        }
+    }
+
+    fn finish(self) -> Result<(), AppError> {
+        match self {
+            SampleWriter::Wav(writer) => writer.finalize().map_err(export_error),
+            SampleWriter::Flac(writer) => writer.finish(),
+        }
+    }
+}
+
+// FLAC file encoded one frame at a time as the render fills blocks, so only a
+// single block is held in memory whatever the length
+struct FlacWriter {
+    file: BufWriter<File>,
+    config: Verified<flacenc::config::Encoder>,
+    // Frame and block sizes seen so far, written to the header at the end
+    stream_info: StreamInfo,
+    framebuf: FrameBuf,
+    // Interleaved samples of the frame being filled
+    block: Vec<i32>,
+    frame_number: usize,
+    total_frames: usize,
+}
+
+impl FlacWriter {
+    fn create(path: &Path) -> Result<Self, AppError> {
+        let config = flacenc::config::Encoder::default()
+            .into_verified()
+            .map_err(|(_, e)| flac_error(e))?;
+        let stream_info = StreamInfo::new(
+            EXPORT_SAMPLE_RATE as usize,
+            CHANNELS as usize,
+            BITS_PER_SAMPLE as usize,
+        )
+        .map_err(flac_error)?;
+        let framebuf =
+            FrameBuf::with_size(CHANNELS as usize, config.block_size).map_err(flac_error)?;
+
+        // Placeholder header, rewritten with the final stream info by `finish`
+        let mut file = BufWriter::new(File::create(path).map_err(export_error)?);
+        write_flac_header(&mut file, &stream_info)?;
+
+        Ok(Self {
+            file,
+            block: Vec::with_capacity(config.block_size * CHANNELS as usize),
+            config,
+            stream_info,
+            framebuf,
+            frame_number: 0,
+            total_frames: 0,
+        })
+    }
+
+    fn write(&mut self, sample: i16) -> Result<(), AppError> {
+        self.block.push(sample as i32);
+        if self.block.len() == self.config.block_size * CHANNELS as usize {
+            self.encode_block()?;
+        }
+        Ok(())
+    }
+
+    // Encode the samples collected so far as the next frame
+    fn encode_block(&mut self) -> Result<(), AppError> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+
+        self.framebuf
+            .fill_interleaved(&self.block)
+            .map_err(flac_error)?;
+        let frame = flacenc::encode_fixed_size_frame(
+            &self.config,
+            &self.framebuf,
+            self.frame_number,
+            &self.stream_info,
+        )
+        .map_err(flac_error)?;
+        self.stream_info.update_frame_info(&frame);
+
+        let mut sink = ByteSink::new();
+        frame.write(&mut sink).map_err(flac_error)?;
+        self.file.write_all(sink.as_slice()).map_err(export_error)?;
+
+        self.frame_number += 1;
+        self.total_frames += self.block.len() / CHANNELS as usize;
+        self.block.clear();
+        Ok(())
+    }
+
+    // The MD5 signature is left unset, which decoders read as "not computed"
+    fn finish(mut self) -> Result<(), AppError> {
+        self.encode_block()?;
+        self.stream_info.set_total_samples(self.total_frames);
+        self.file.seek(SeekFrom::Start(0)).map_err(export_error)?;
+        write_flac_header(&mut self.file, &self.stream_info)?;
+        self.file.flush().map_err(export_error)
+    }
+}
+
+// `fLaC` marker and stream info block, always the same size
+fn write_flac_header(file: &mut impl Write, stream_info: &StreamInfo) -> Result<(), AppError> {
+    let mut sink = ByteSink::new();
+    Stream::with_stream_info(stream_info.clone())
+        .write(&mut sink)
+        .map_err(flac_error)?;
+    file.write_all(sink.as_slice()).map_err(export_error)
+}
+
+// Variation track rendered from static sounds, each take decoded once
+struct OfflineVariations {
+    files: Vec<PathBuf>,
+    decoded: HashMap<usize, StaticSoundData>,
+    rotation: Rotation,
+    handle: Option<StaticSoundHandle>,
+    volume: f32,
+    pan: f32,
+    rate: f32,
+}
+
+impl OfflineVariations {
+    fn new(
+        folder: &Path,
+        settings: VariationSettings,
+        volume: f32,
+        pan: f32,
+        rate: f32,
+    ) -> Result<Self, AppError> {
+        let files = folder_clips(folder)?;
+        Ok(Self {
+            rotation: Rotation::new(files.len(), settings.crossfade(), fastrand::Rng::new()),
+            files,
+            decoded: HashMap::new(),
+            handle: None,
+            volume,
+            pan,
+            rate,
+        })
+    }
+
+    // Start the first take, or crossfade into the next one once it is due
+    fn advance(&mut self, elapsed: Duration, track: &mut TrackHandle) -> Result<(), AppError> {
+        let (index, fade_in) = match self.handle {
+            None => (self.rotation.current(), None),
+            Some(_) => match self.rotation.advance(elapsed) {
+                Some(index) => (index, Some(self.rotation.fade())),
+                None => return Ok(()),
+            },
+        };
+
+        let data = match self.decoded.get(&index) {
+            Some(data) => data.clone(),
+            None => {
+                let data = StaticSoundData::from_file(&self.files[index])
+                    .map_err(|e| AppError::Audio(e.into()))?;
+                self.decoded.insert(index, data.clone());
+                data
+            }
+        };
+        let duration = data.duration().div_f32(self.rate);
+        let tween = fade_in.map(|duration| Tween {
+            duration,
+            ..Tween::default()
+        });
+        let settings = StaticSoundSettings::new()
+            .fade_in_tween(tween)
+            .volume(self.volume)
+            .panning(Panning(self.pan))
+            .playback_rate(PlaybackRate(self.rate as f64));
+
+        let handle = track
+            .play(data.with_settings(settings))
+            .map_err(export_error)?;
+        if let (Some(mut previous), Some(tween)) = (self.handle.replace(handle), tween) {
+            previous.stop(tween);
+        }
+        self.rotation.started(duration);
+        Ok(())
+    }
+}
+
+// Render the playing tracks of the job into its file
+pub fn render(job: &ExportJob) -> Result<(), AppError> {
+    let mut main_track_builder = MainTrackBuilder::new();
+    let _master_bus = MasterBus::new(&mut main_track_builder, &job.settings.limiter);
+    let mut manager = AudioManager::<OfflineBackend>::new(AudioManagerSettings {
+        backend_settings: EXPORT_SAMPLE_RATE,
+        main_track_builder,
+        ..AudioManagerSettings::default()
+    })
+    .map_err(|_| AppError::Audio(AudioError::InitializationFailed))?;
+
+    // Track handles have to stay alive, dropping one removes its sub-track
+    let mut chains = Vec::new();
+    // Event and variation tracks with their chain index, stepped as the render clock advances
+    let mut layers = Vec::new();
+    let mut rotations = Vec::new();
+    let mut drifts = Vec::new();
+    for (track_id, track) in job.tracks.iter().enumerate() {
+        if !matches!(track.state, PlaybackState::Playing) {
+            continue;
+        }
+
+        let mut chain = ToneChain::new(&mut manager, &track.tone)?;
+        if !track.drift.is_off() {
+            let drift = Drift::new(&mut manager, track.drift)?;
+            drift.attach(&mut chain.track, Tween::default());
+            drifts.push(drift);
+        }
+
+        let volume = effective_volume(track_id, &job.tracks, &job.settings);
+        let rate = track.playback_rate;
+        let loop_region = job.settings.loop_region.clone().unwrap_or(0.0..);
+        match &track.source {
+            // Streams and clips scheduled in real time become static sounds the render
+            // clock can drive
+            SoundSource::File(path) => {
+                let data =
+                    StaticSoundData::from_file(path).map_err(|e| AppError::Audio(e.into()))?;
+                let settings = StaticSoundSettings::new()
+                    .volume(volume)
+                    .panning(Panning(track.pan))
+                    .playback_rate(PlaybackRate(rate as f64))
+                    .loop_region(loop_region);
+                chain
+                    .track
+                    .play(data.with_settings(settings))
+                    .map_err(export_error)?;
+            }
+            SoundSource::Events(folder, event) => {
+                let layer = EventLayer::load(folder, *event, volume, track.pan, rate)?;
+                layers.push((chains.len(), layer));
+            }
+            SoundSource::Variations(folder, variations) => {
+                let rotation =
+                    OfflineVariations::new(folder, *variations, volume, track.pan, rate)?;
+                rotations.push((chains.len(), rotation));
+            }
+            // Synthesized sources render just like they play
+            source @ (SoundSource::Granular(..) | SoundSource::Generator(_)) => {
+                let start = Start {
+                    volume,
+                    pan: track.pan,
+                    rate,
+                    fade_in: None,
+                    loop_region,
+                };
+                source.start(Output::<OfflineBackend>::Track(&mut chain.track), start)?;
+            }
+        }
+        chains.push(chain);
+    }
+
+    let mut renderer = manager
+        .backend_mut()
+        .renderer
+        .take()
+        .ok_or_else(|| export_error("renderer was not started"))?;
+
+    let total_frames = (job.options.duration.as_secs_f64() * EXPORT_SAMPLE_RATE as f64) as usize;
+    let fade_frames = if job.options.fade {
+        ((EXPORT_FADE.as_secs_f64() * EXPORT_SAMPLE_RATE as f64) as usize).min(total_frames / 2)
+    } else {
+        0
+    };
+
+    let mut writer = SampleWriter::create(&job.path, job.options.format)?;
+    let mut buffer = vec![0.0; RENDER_BLOCK_FRAMES * CHANNELS as usize];
+    let mut frame = 0;
+    while frame < total_frames {
+        let frames = RENDER_BLOCK_FRAMES.min(total_frames - frame);
+        let elapsed = Duration::from_secs_f64(frames as f64 / EXPORT_SAMPLE_RATE as f64);
+        for (chain, layer) in &mut layers {
+            let track = &mut chains[*chain].track;
+            layer.advance(elapsed, |clip| track.play(clip));
+        }
+        for (chain, rotation) in &mut rotations {
+            rotation.advance(elapsed, &mut chains[*chain].track)?;
+        }
+        for drift in &mut drifts {
+            drift.advance(elapsed);
+        }
+
+        let block = &mut buffer[..frames * CHANNELS as usize];
+        renderer.on_start_processing();
+        renderer.process(block, CHANNELS);
+
+        for (offset, samples) in block.chunks_exact(CHANNELS as usize).enumerate() {
+            let gain = fade_gain(frame + offset, total_frames, fade_frames);
+            for sample in samples {
+                writer.write(sample * gain)?;
+            }
+        }
+        frame += frames;
+    }
+
+    writer.finish()?;
+    log::info!("Exported {} tracks to {}", chains.len(), job.path.display());
+    Ok(())
+}
+
+// Render on a blocking thread, returning the written file
+pub async fn export(job: ExportJob) -> Result<PathBuf, AppError> {
+    tokio::task::spawn_blocking(move || render(&job).map(|()| job.path))
+        .await
+        .map_err(export_error)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_gain() {
+        assert_eq!(fade_gain(0, 100, 0), 1.0);
+        assert_eq!(fade_gain(0, 100, 10), 0.0);
+        assert_eq!(fade_gain(5, 100, 10), 0.5);
+        assert_eq!(fade_gain(50, 100, 10), 1.0);
+        assert_eq!(fade_gain(95, 100, 10), 0.5);
+    }
+
+    #[test]
+    fn test_render_silent_mix() {
+        let path = std::env::temp_dir().join("cosmic-noise-export-test.wav");
+        let job = ExportJob {
+            tracks: vec![],
+            settings: AudioSettings::default(),
+            options: ExportOptions {
+                duration: Duration::from_millis(500),
+                fade: true,
+                format: ExportFormat::Wav,
+            },
+            path: path.clone(),
+        };
+
+        render(&job).unwrap();
+
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, EXPORT_SAMPLE_RATE);
+        assert_eq!(reader.duration(), EXPORT_SAMPLE_RATE / 2);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_render_flac() {
+        let path = std::env::temp_dir().join("cosmic-noise-export-test.flac");
+        let job = ExportJob {
+            tracks: vec![],
+            settings: AudioSettings::default(),
+            options: ExportOptions {
+                duration: Duration::from_millis(500),
+                fade: false,
+                format: ExportFormat::Flac,
+            },
+            path: path.clone(),
+        };
+
+        render(&job).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"fLaC"));
+        // Total samples are the low 36 bits of stream info bytes 13..18
+        let info = &bytes[8..42];
+        let total = info[13..18]
+            .iter()
+            .fold(0u64, |total, byte| total << 8 | *byte as u64)
+            & 0xf_ffff_ffff;
+        assert_eq!(total, EXPORT_SAMPLE_RATE as u64 / 2);
+        let _ = std::fs::remove_file(path);
+    }
+}
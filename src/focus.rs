@@ -0,0 +1,247 @@
+//! Pomodoro style focus sessions.
+//!
+//! A session alternates between work and break phases, each crossfading to
+//! its own preset. Phase changes are announced with a short synthesized chime
+//! and a desktop notification. Completed work phases are counted per day in
+//! `$XDG_DATA_HOME/cosmic-noise/focus_history.toml`.
+
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDate;
+use kira::Frame;
+use kira::sound::static_sound::{StaticSoundData, StaticSoundSettings};
+use serde::{Deserialize, Serialize};
+use zbus::zvariant::Value;
+
+use crate::config::ConfigManager;
+use crate::fl;
+use crate::models::FocusSettings;
+
+const HISTORY_FILE: &str = "cosmic-noise/focus_history.toml";
+
+const CHIME_SAMPLE_RATE: u32 = 48_000;
+// Two rising notes, in Hz
+const CHIME_NOTES: [f32; 2] = [880.0, 1_318.5];
+const CHIME_NOTE_LENGTH: Duration = Duration::from_millis(600);
+// Peak level of the chime, well below full scale
+const CHIME_AMPLITUDE: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPhase {
+    Work,
+    Break,
+}
+
+impl FocusPhase {
+    // Phase following this one
+    pub fn next(self) -> Self {
+        match self {
+            FocusPhase::Work => FocusPhase::Break,
+            FocusPhase::Break => FocusPhase::Work,
+        }
+    }
+
+    pub fn duration(self, settings: &FocusSettings) -> Duration {
+        let minutes = match self {
+            FocusPhase::Work => settings.work_minutes,
+            FocusPhase::Break => settings.break_minutes,
+        };
+        Duration::from_secs(minutes as u64 * 60)
+    }
+
+    // Preset played during this phase, if any
+    pub fn preset(self, settings: &FocusSettings) -> Option<&str> {
+        match self {
+            FocusPhase::Work => settings.work_preset.as_deref(),
+            FocusPhase::Break => settings.break_preset.as_deref(),
+        }
+    }
+}
+
+impl fmt::Display for FocusPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FocusPhase::Work => write!(f, "{}", fl!("focus")),
+            FocusPhase::Break => write!(f, "{}", fl!("focus-break")),
+        }
+    }
+}
+
+// Phase currently running and when it ends
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocusSession {
+    pub phase: FocusPhase,
+    pub ends_at: Instant,
+}
+
+impl FocusSession {
+    pub fn start(phase: FocusPhase, settings: &FocusSettings, now: Instant) -> Self {
+        Self {
+            phase,
+            ends_at: now + phase.duration(settings),
+        }
+    }
+
+    pub fn is_over(&self, now: Instant) -> bool {
+        now >= self.ends_at
+    }
+
+    // Countdown shown in the toolbar, e.g. "Focus 24:59"
+    pub fn label(&self, now: Instant) -> String {
+        let remaining = self.ends_at.saturating_duration_since(now).as_secs();
+        format!("{} {:02}:{:02}", self.phase, remaining / 60, remaining % 60)
+    }
+}
+
+// Number of completed work phases on one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FocusDay {
+    date: NaiveDate,
+    cycles: u32,
+}
+
+// Completed cycles of previous sessions
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct FocusHistory {
+    #[serde(default)]
+    days: Vec<FocusDay>,
+}
+
+impl FocusHistory {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(HISTORY_FILE))
+    }
+
+    pub fn load() -> Self {
+        Self::path()
+            .map(|path| ConfigManager::load_toml(&path))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Err(e) = ConfigManager::save_toml(&path, self) {
+            log::warn!("Failed to save focus history: {e}");
+        }
+    }
+
+    // Count a completed work phase
+    pub fn record(&mut self, date: NaiveDate) {
+        match self.days.iter_mut().find(|day| day.date == date) {
+            Some(day) => day.cycles += 1,
+            None => self.days.push(FocusDay { date, cycles: 1 }),
+        }
+    }
+
+    pub fn cycles_on(&self, date: NaiveDate) -> u32 {
+        self.days
+            .iter()
+            .find(|day| day.date == date)
+            .map_or(0, |day| day.cycles)
+    }
+
+    pub fn total(&self) -> u32 {
+        self.days.iter().map(|day| day.cycles).sum()
+    }
+}
+
+// Short two note chime announcing a phase change
+pub fn chime() -> StaticSoundData {
+    let note_frames = (CHIME_NOTE_LENGTH.as_secs_f32() * CHIME_SAMPLE_RATE as f32) as usize;
+    let frames: Vec<Frame> = CHIME_NOTES
+        .iter()
+        .flat_map(|frequency| {
+            (0..note_frames).map(move |index| {
+                let time = index as f32 / CHIME_SAMPLE_RATE as f32;
+                // Quick exponential decay, like a struck bell
+                let envelope = (-6.0 * time).exp();
+                let sample = (TAU * frequency * time).sin() * envelope * CHIME_AMPLITUDE;
+                Frame::from_mono(sample)
+            })
+        })
+        .collect();
+
+    StaticSoundData {
+        sample_rate: CHIME_SAMPLE_RATE,
+        frames: frames.into(),
+        settings: StaticSoundSettings::default(),
+        slice: None,
+    }
+}
+
+// Show a desktop notification, without waiting for the notification daemon
+pub fn notify(summary: String, body: String) {
+    std::thread::spawn(move || {
+        let result = zbus::blocking::Connection::session().and_then(|connection| {
+            connection.call_method(
+                Some("org.freedesktop.Notifications"),
+                "/org/freedesktop/Notifications",
+                Some("org.freedesktop.Notifications"),
+                "Notify",
+                &(
+                    "Cosmic Noise",
+                    0u32,
+                    "",
+                    summary.as_str(),
+                    body.as_str(),
+                    Vec::<&str>::new(),
+                    HashMap::<&str, Value>::new(),
+                    -1i32,
+                ),
+            )
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to show notification: {e}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_countdown() {
+        let settings = FocusSettings::default();
+        let now = Instant::now();
+        let session = FocusSession::start(FocusPhase::Work, &settings, now);
+
+        assert_eq!(session.label(now), "Focus 25:00");
+        assert_eq!(session.label(now + Duration::from_secs(61)), "Focus 23:59");
+        assert!(!session.is_over(now));
+        assert!(session.is_over(now + Duration::from_secs(25 * 60)));
+        assert_eq!(session.phase.next(), FocusPhase::Break);
+    }
+
+    #[test]
+    fn test_history() {
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let tuesday = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let mut history = FocusHistory::default();
+        history.record(monday);
+        history.record(monday);
+        history.record(tuesday);
+
+        assert_eq!(history.cycles_on(monday), 2);
+        assert_eq!(history.cycles_on(tuesday), 1);
+        assert_eq!(history.total(), 3);
+    }
+
+    #[test]
+    fn test_chime() {
+        let chime = chime();
+        assert_eq!(chime.frames.len(), 2 * 28_800);
+        assert!(
+            chime
+                .frames
+                .iter()
+                .all(|frame| frame.left.abs() <= CHIME_AMPLITUDE)
+        );
+    }
+}
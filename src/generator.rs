@@ -0,0 +1,274 @@
+//! Binaural beat and isochronic tone generator.
+//!
+//! The generator track has no file: its tone is synthesized by a custom kira
+//! sound. In binaural mode each ear gets the carrier shifted by half the beat
+//! frequency in opposite directions, so the brain perceives a beat at their
+//! difference. In isochronic mode one tone is pulsed on and off at the beat
+//! frequency. In noise mode each ear gets its own white noise, filtered to the
+//! chosen color.
+
+use std::convert::Infallible;
+use std::f64::consts::TAU;
+
+use kira::info::Info;
+use kira::sound::{Sound, SoundData};
+use kira::{Frame, Tween};
+
+use crate::models::{GeneratorMode, GeneratorSettings, GeneratorWaveform, NoiseColor};
+use crate::synth::{Synth, SynthHandle};
+
+// Peak level of the tone before the track volume, leaving room in the mix
+const GENERATOR_LEVEL: f32 = 0.5;
+
+// Gains bringing the noise colors to roughly the same loudness
+const WHITE_GAIN: f32 = 0.35;
+const PINK_GAIN: f32 = 0.12;
+const BROWN_GAIN: f32 = 3.5;
+
+// Control of a playing generator
+pub type GeneratorHandle = SynthHandle<GeneratorSettings>;
+
+// One sample of `waveform` at `phase` (0.0 to 1.0)
+pub fn oscillator(waveform: GeneratorWaveform, phase: f64) -> f32 {
+    let phase = phase.fract();
+    let sample = match waveform {
+        GeneratorWaveform::Sine => (TAU * phase).sin(),
+        GeneratorWaveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        GeneratorWaveform::Square => {
+            if phase < 0.5 {
+                1.0
+            } else {
+                -1.0
+            }
+        }
+    };
+    sample as f32
+}
+
+// Smooth on/off envelope of an isochronic pulse at `phase` (0.0 to 1.0)
+pub fn pulse(phase: f64) -> f32 {
+    (0.5 - 0.5 * (TAU * phase.fract()).cos()) as f32
+}
+
+// Filter memory of the noise in one ear
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Noise {
+    pink: [f32; 3],
+    brown: f32,
+}
+
+impl Noise {
+    // Next sample of `color`, filtered from a `white` sample between -1.0 and 1.0
+    pub fn next(&mut self, color: NoiseColor, white: f32) -> f32 {
+        match color {
+            NoiseColor::White => white * WHITE_GAIN,
+            // Paul Kellet's economy filter, within 0.5 dB of pink above 10 Hz
+            NoiseColor::Pink => {
+                let [b0, b1, b2] = &mut self.pink;
+                *b0 = 0.99765 * *b0 + white * 0.099046;
+                *b1 = 0.963 * *b1 + white * 0.2965164;
+                *b2 = 0.57 * *b2 + white * 1.0526913;
+                (*b0 + *b1 + *b2 + white * 0.1848) * PINK_GAIN
+            }
+            // Leaky integrator, so the rumble never drifts away from zero
+            NoiseColor::Brown => {
+                self.brown = (self.brown + 0.02 * white) / 1.02;
+                self.brown * BROWN_GAIN
+            }
+        }
+    }
+}
+
+// Sound data played on the generator track's sub-track
+pub struct GeneratorData {
+    settings: GeneratorSettings,
+    volume: f32,
+    pan: f32,
+    fade_in: Option<Tween>,
+}
+
+impl GeneratorData {
+    pub fn new(settings: GeneratorSettings, volume: f32, pan: f32, fade_in: Option<Tween>) -> Self {
+        Self {
+            settings,
+            volume,
+            pan,
+            fade_in,
+        }
+    }
+}
+
+impl SoundData for GeneratorData {
+    type Error = Infallible;
+    type Handle = GeneratorHandle;
+
+    fn into_sound(self) -> Result<(Box<dyn Sound>, Self::Handle), Self::Error> {
+        // Tones keep their frequencies whatever the playback rate, the generator has its
+        // own pitch controls
+        let (synth, handle) = Synth::new(self.settings, self.volume, self.pan, 1.0, self.fade_in);
+        let sound = GeneratorSound {
+            synth,
+            phases: [0.0; 3],
+            noise: [Noise::default(); 2],
+            rng: fastrand::Rng::new(),
+        };
+        Ok((Box::new(sound), handle))
+    }
+}
+
+struct GeneratorSound {
+    synth: Synth<GeneratorSettings>,
+    // Left tone, right tone and pulse phases
+    phases: [f64; 3],
+    // Left and right noise
+    noise: [Noise; 2],
+    rng: fastrand::Rng,
+}
+
+impl GeneratorSound {
+    fn white(&mut self) -> f32 {
+        self.rng.f32() * 2.0 - 1.0
+    }
+}
+
+impl Sound for GeneratorSound {
+    fn on_start_processing(&mut self) {
+        // Frequency changes apply at once, the phases keep running so there is no click
+        self.synth.refresh();
+    }
+
+    fn process(&mut self, out: &mut [Frame], dt: f64, _info: &Info) {
+        let settings = self.synth.settings();
+        let carrier = settings.carrier as f64;
+        let beat = settings.beat as f64;
+        let frequencies = match settings.mode {
+            GeneratorMode::Binaural => [carrier - beat / 2.0, carrier + beat / 2.0, 0.0],
+            GeneratorMode::Isochronic => [carrier, carrier, beat],
+            GeneratorMode::Noise => [0.0; 3],
+        };
+
+        let (left_pan, right_pan) = self.synth.pan_gains();
+        let ramp = self.synth.ramp(dt);
+        for frame in out.iter_mut() {
+            let (left, right, envelope) = match settings.mode {
+                GeneratorMode::Binaural => (
+                    oscillator(settings.waveform, self.phases[0]),
+                    oscillator(settings.waveform, self.phases[1]),
+                    1.0,
+                ),
+                GeneratorMode::Isochronic => (
+                    oscillator(settings.waveform, self.phases[0]),
+                    oscillator(settings.waveform, self.phases[1]),
+                    pulse(self.phases[2]),
+                ),
+                GeneratorMode::Noise => {
+                    let (white_left, white_right) = (self.white(), self.white());
+                    (
+                        self.noise[0].next(settings.noise, white_left),
+                        self.noise[1].next(settings.noise, white_right),
+                        1.0,
+                    )
+                }
+            };
+            let level = self.synth.next_gain(ramp) * envelope * GENERATOR_LEVEL;
+            *frame = Frame {
+                left: left * level * left_pan,
+                right: right * level * right_pan,
+            };
+
+            for (phase, frequency) in self.phases.iter_mut().zip(frequencies) {
+                *phase = (*phase + frequency * dt).fract();
+            }
+        }
+
+        self.synth.settle();
+    }
+
+    fn finished(&self) -> bool {
+        self.synth.finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kira::info::MockInfoBuilder;
+    use kira::sound::PlaybackState;
+
+    #[test]
+    fn test_oscillator() {
+        assert!(oscillator(GeneratorWaveform::Sine, 0.0).abs() < 1e-6);
+        assert!((oscillator(GeneratorWaveform::Sine, 0.25) - 1.0).abs() < 1e-6);
+        assert_eq!(oscillator(GeneratorWaveform::Triangle, 0.5), 1.0);
+        assert_eq!(oscillator(GeneratorWaveform::Triangle, 0.0), -1.0);
+        assert_eq!(oscillator(GeneratorWaveform::Square, 0.75), -1.0);
+        // Phases wrap around
+        assert_eq!(oscillator(GeneratorWaveform::Square, 1.25), 1.0);
+    }
+
+    #[test]
+    fn test_pulse() {
+        assert_eq!(pulse(0.0), 0.0);
+        assert_eq!(pulse(0.5), 1.0);
+        assert!(pulse(0.25) > 0.4 && pulse(0.25) < 0.6);
+    }
+
+    #[test]
+    fn test_noise_colors() {
+        let mut rng = fastrand::Rng::with_seed(3);
+        for color in NoiseColor::all() {
+            let mut noise = Noise::default();
+            let samples: Vec<f32> = (0..48_000)
+                .map(|_| noise.next(*color, rng.f32() * 2.0 - 1.0))
+                .collect();
+            let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+
+            // Audible, at about the same level, and without clipping
+            assert!(rms > 0.1 && rms < 0.35, "{color:?} rms {rms}");
+            assert!(samples.iter().all(|s| s.abs() < 1.0));
+        }
+    }
+
+    #[test]
+    fn test_noise_mode() {
+        let settings = GeneratorSettings {
+            mode: GeneratorMode::Noise,
+            ..GeneratorSettings::default()
+        };
+        let (mut sound, _handle) = GeneratorData::new(settings, 0.0, 0.0, None)
+            .into_sound()
+            .unwrap();
+        let mut out = vec![Frame::ZERO; 4_800];
+        sound.on_start_processing();
+        sound.process(&mut out, 1.0 / 48_000.0, &MockInfoBuilder::new().build());
+
+        // Each ear gets its own noise
+        assert!(out.iter().any(|frame| frame.left.abs() > 0.01));
+        assert!(
+            out.iter()
+                .any(|frame| (frame.left - frame.right).abs() > 0.01)
+        );
+    }
+
+    #[test]
+    fn test_binaural_ears() {
+        let data = GeneratorData::new(GeneratorSettings::default(), 0.0, 0.0, None);
+        let (mut sound, mut handle) = data.into_sound().unwrap();
+        let mut out = vec![Frame::ZERO; 4_800];
+        sound.on_start_processing();
+        sound.process(&mut out, 1.0 / 48_000.0, &MockInfoBuilder::new().build());
+
+        // Both ears play, at slightly different frequencies
+        assert!(out.iter().any(|frame| frame.left.abs() > 0.1));
+        assert!(
+            out.iter()
+                .any(|frame| (frame.left - frame.right).abs() > 0.01)
+        );
+
+        handle.stop(Tween::default());
+        sound.on_start_processing();
+        sound.process(&mut out, 1.0 / 48_000.0, &MockInfoBuilder::new().build());
+        assert!(sound.finished());
+        assert_eq!(handle.state(), PlaybackState::Stopped);
+    }
+}
@@ -0,0 +1,362 @@
+//! Granular textures: an endless, never repeating sound made from one file.
+//!
+//! Short windowed grains are cut from the decoded file and overlapped. A
+//! playhead moves through the file at normal speed and every grain starts at a
+//! random distance from it, optionally detuned, so even a short clip becomes a
+//! texture without an audible loop point.
+
+use std::convert::Infallible;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use kira::info::Info;
+use kira::sound::{Sound, SoundData};
+use kira::{Frame, Tween};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::errors::{AppError, AudioError};
+use crate::models::{GrainWindow, GranularSettings, MIN_GRAIN_DENSITY};
+use crate::synth::{Synth, SynthHandle};
+
+// Most grains sounding at once, caps the work per frame
+const MAX_GRAINS: usize = 64;
+// Longest part of a file grains are read from, decoding stops there
+const MAX_SOURCE_SECONDS: u32 = 60;
+// Share of a Tukey window spent fading in, and again fading out
+const TUKEY_FADE: f64 = 0.2;
+
+// Control of a playing granular texture
+pub type GranularHandle = SynthHandle<GranularSettings>;
+
+// Gain of the `shape` window at `t` through a grain (0.0 to 1.0)
+pub fn window(shape: GrainWindow, t: f64) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    let gain = match shape {
+        GrainWindow::Hann => 0.5 - 0.5 * (2.0 * PI * t).cos(),
+        GrainWindow::Triangle => 1.0 - (2.0 * t - 1.0).abs(),
+        GrainWindow::Tukey => {
+            let edge = t.min(1.0 - t) / TUKEY_FADE;
+            if edge >= 1.0 {
+                1.0
+            } else {
+                0.5 - 0.5 * (PI * edge).cos()
+            }
+        }
+    };
+    gain as f32
+}
+
+// Frame at a fractional `position`, blending the two nearest frames
+fn sample(frames: &[Frame], position: f64) -> Frame {
+    let index = (position as usize).min(frames.len() - 1);
+    let next = frames[(index + 1) % frames.len()];
+    let fraction = (position - index as f64) as f32;
+    frames[index] * (1.0 - fraction) + next * fraction
+}
+
+// Sound data of a granular texture, played on the track's sub-track
+pub struct GranularData {
+    frames: Arc<[Frame]>,
+    sample_rate: u32,
+    settings: GranularSettings,
+    volume: f32,
+    pan: f32,
+    rate: f32,
+    fade_in: Option<Tween>,
+}
+
+impl GranularData {
+    // Decode the start of the file up front, grains are read from anywhere in it
+    pub fn load(
+        path: &Path,
+        settings: GranularSettings,
+        volume: f32,
+        pan: f32,
+        rate: f32,
+        fade_in: Option<Tween>,
+    ) -> Result<Self, AppError> {
+        let (frames, sample_rate) = decode_start(path)?;
+        if frames.is_empty() {
+            return Err(AppError::Audio(AudioError::PlaybackError(format!(
+                "{} has no audio to make grains from",
+                path.display()
+            ))));
+        }
+
+        Ok(Self {
+            frames: frames.into(),
+            sample_rate,
+            settings,
+            volume,
+            pan,
+            rate,
+            fade_in,
+        })
+    }
+}
+
+fn decoder_error(error: impl std::fmt::Display) -> AppError {
+    AppError::Audio(AudioError::DecoderError(error.to_string()))
+}
+
+// Decode at most `MAX_SOURCE_SECONDS` from the start of a file, so a long
+// recording costs no more than its first minute
+fn decode_start(path: &Path) -> Result<(Vec<Frame>, u32), AppError> {
+    let file =
+        File::open(path).map_err(|e| AppError::Audio(AudioError::PlaybackError(e.to_string())))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(decoder_error)?
+        .format;
+
+    let track = format
+        .default_track()
+        .ok_or(AppError::Audio(AudioError::NoDefaultTrack))?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or(AppError::Audio(AudioError::UnknownSampleRate))?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(decoder_error)?;
+
+    let limit = (sample_rate * MAX_SOURCE_SECONDS) as usize;
+    let mut frames = Vec::new();
+    while frames.len() < limit {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(decoder_error(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A damaged packet only costs its few milliseconds
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(decoder_error(e)),
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count();
+        if channels == 0 {
+            return Err(AppError::Audio(AudioError::UnsupportedChannelConfiguration));
+        }
+        let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        samples.copy_interleaved_ref(decoded);
+        // Mono is played on both sides, anything past stereo is dropped
+        frames.extend(
+            samples
+                .samples()
+                .chunks_exact(channels)
+                .map(|frame| Frame::new(frame[0], frame[frame.len().min(2) - 1])),
+        );
+    }
+
+    frames.truncate(limit);
+    Ok((frames, sample_rate))
+}
+
+impl SoundData for GranularData {
+    type Error = Infallible;
+    type Handle = GranularHandle;
+
+    fn into_sound(self) -> Result<(Box<dyn Sound>, Self::Handle), Self::Error> {
+        let (synth, handle) = Synth::new(
+            self.settings,
+            self.volume,
+            self.pan,
+            self.rate,
+            self.fade_in,
+        );
+        let mut rng = fastrand::Rng::new();
+        let sound = GranularSound {
+            synth,
+            playhead: rng.f64() * self.frames.len() as f64,
+            frames: self.frames,
+            sample_rate: self.sample_rate as f64,
+            grains: Vec::with_capacity(MAX_GRAINS),
+            countdown: 0.0,
+            rng,
+        };
+        Ok((Box::new(sound), handle))
+    }
+}
+
+struct Grain {
+    // Read position in source frames
+    position: f64,
+    // Source frames read per output frame
+    speed: f64,
+    // Output frames played so far and in total
+    age: usize,
+    length: usize,
+}
+
+struct GranularSound {
+    synth: Synth<GranularSettings>,
+    frames: Arc<[Frame]>,
+    sample_rate: f64,
+    // Position in source frames the grains are scattered around
+    playhead: f64,
+    grains: Vec<Grain>,
+    // Seconds until the next grain starts
+    countdown: f64,
+    rng: fastrand::Rng,
+}
+
+impl GranularSound {
+    fn spawn(&mut self, settings: GranularSettings, length: usize, speed: f64) -> Grain {
+        let len = self.frames.len() as f64;
+        let offset = (self.rng.f64() * 2.0 - 1.0) * settings.position_jitter as f64 * len;
+        let semitones = (self.rng.f64() * 2.0 - 1.0) * settings.pitch_jitter as f64;
+        Grain {
+            position: (self.playhead + offset).rem_euclid(len),
+            speed: speed * 2f64.powf(semitones / 12.0),
+            age: 0,
+            length,
+        }
+    }
+}
+
+impl Sound for GranularSound {
+    fn on_start_processing(&mut self) {
+        self.synth.refresh();
+    }
+
+    fn process(&mut self, out: &mut [Frame], dt: f64, _info: &Info) {
+        let settings = self.synth.settings();
+        let len = self.frames.len() as f64;
+        // New grains pick up a rate change, so it blends in over a grain length
+        let speed = self.sample_rate * dt * self.synth.rate() as f64;
+        let grain_seconds = settings.grain_size as f64 / 1000.0;
+        let length = (grain_seconds / dt).max(1.0) as usize;
+        let interval = 1.0 / settings.density.max(MIN_GRAIN_DENSITY) as f64;
+        // Overlapping grains add up, keep the level steady whatever the density
+        let overlap = (settings.density as f64 * grain_seconds).max(1.0);
+        let level = (1.0 / overlap.sqrt()) as f32;
+
+        let (left_pan, right_pan) = self.synth.pan_gains();
+        let ramp = self.synth.ramp(dt);
+        for frame in out.iter_mut() {
+            self.countdown -= dt;
+            if self.countdown <= 0.0 {
+                // Irregular spacing keeps the grains from buzzing at the density rate
+                self.countdown += interval * (0.5 + self.rng.f64());
+                if self.grains.len() < MAX_GRAINS {
+                    let grain = self.spawn(settings, length, speed);
+                    self.grains.push(grain);
+                }
+            }
+
+            let mut sum = Frame::ZERO;
+            for grain in &mut self.grains {
+                let t = grain.age as f64 / grain.length as f64;
+                sum += sample(&self.frames, grain.position) * window(settings.window, t);
+                grain.position = (grain.position + grain.speed) % len;
+                grain.age += 1;
+            }
+            self.grains.retain(|grain| grain.age < grain.length);
+
+            let gain = self.synth.next_gain(ramp) * level;
+            *frame = Frame {
+                left: sum.left * gain * left_pan,
+                right: sum.right * gain * right_pan,
+            };
+            self.playhead = (self.playhead + speed) % len;
+        }
+
+        self.synth.settle();
+    }
+
+    fn finished(&self) -> bool {
+        self.synth.finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kira::info::MockInfoBuilder;
+
+    #[test]
+    fn test_windows() {
+        for shape in GrainWindow::all() {
+            // Grains start and end silent, and peak in the middle
+            assert!(window(*shape, 0.0).abs() < 1e-6);
+            assert!(window(*shape, 1.0).abs() < 1e-6);
+            assert!((window(*shape, 0.5) - 1.0).abs() < 1e-6);
+        }
+        assert_eq!(window(GrainWindow::Tukey, 0.3), 1.0);
+        assert!(window(GrainWindow::Hann, 0.3) < 1.0);
+    }
+
+    #[test]
+    fn test_decode_start() {
+        // A mono file just over the limit
+        let path = std::env::temp_dir().join("cosmic-noise-granular-test.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..8_000 * (MAX_SOURCE_SECONDS + 1) {
+            writer.write_sample(i16::MAX / 2).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (frames, sample_rate) = decode_start(&path).unwrap();
+        assert_eq!(sample_rate, 8_000);
+        assert_eq!(frames.len(), (8_000 * MAX_SOURCE_SECONDS) as usize);
+        assert!((frames[0].left - 0.5).abs() < 0.01);
+        assert_eq!(frames[0].left, frames[0].right);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_texture() {
+        // Half a second of a constant signal
+        let data = GranularData {
+            frames: vec![Frame::from_mono(0.5); 24_000].into(),
+            sample_rate: 48_000,
+            settings: GranularSettings {
+                pitch_jitter: 7.0,
+                ..GranularSettings::default()
+            },
+            volume: 0.0,
+            pan: 0.0,
+            rate: 1.0,
+            fade_in: None,
+        };
+        let (mut sound, _handle) = data.into_sound().unwrap();
+        let mut out = vec![Frame::ZERO; 48_000];
+        sound.on_start_processing();
+        sound.process(&mut out, 1.0 / 48_000.0, &MockInfoBuilder::new().build());
+
+        // Grains keep coming long after the source ran out
+        assert!(out[40_000..].iter().any(|frame| frame.left > 0.1));
+    }
+}
@@ -0,0 +1,97 @@
+//! Running the audio engine without a window.
+//!
+//! The headless daemon loads the tracks, replays the startup command (e.g.
+//! `preset Evening`) and then only reacts to requests from the control socket
+//! and D-Bus. SIGTERM, Ctrl+C and the `quit` command fade everything out
+//! before exiting.
+
+use std::time::Duration;
+
+use iced::futures::StreamExt;
+use iced::futures::future::BoxFuture;
+#[cfg(unix)]
+use tokio::signal::unix::{SignalKind, signal};
+
+use crate::app::{CosmicNoise, Message};
+use crate::audio::AudioCommand;
+use crate::ipc::{self, Request};
+use crate::pcm::PcmOutput;
+use crate::utils::files;
+
+// Fade applied to all tracks when the daemon is asked to exit
+const SHUTDOWN_FADE: Duration = Duration::from_secs(3);
+
+pub fn run(pcm_output: Option<PcmOutput>) -> std::io::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    runtime.block_on(async {
+        let mut app = CosmicNoise::init(pcm_output);
+        app.window_visible = false;
+        let mut requests = ipc::requests();
+        let exit = exit_signal()?;
+        tokio::pin!(exit);
+        let mut ticks = tokio::time::interval(Duration::from_secs(1));
+
+        // Tasks returned by `update` only concern the window, so they are dropped
+        let _ = app.update(Message::Loaded(files::load_data().await));
+        log::info!("Running headless with {} tracks", app.track_list.len());
+        let mut analysis = app.loudness_analysis();
+
+        loop {
+            tokio::select! {
+                Some(incoming) = requests.next() => {
+                    if incoming.request == Request::Quit {
+                        if let Some(reply) = incoming.reply {
+                            reply.send(Ok(()));
+                        }
+                        break;
+                    }
+                    let _ = app.update(Message::Remote(incoming));
+                }
+                measured = wait_for(&mut analysis) => {
+                    analysis = None;
+                    app.process_audio_command(AudioCommand::SetLoudness(measured));
+                }
+                _ = ticks.tick() => {
+                    let _ = app.update(Message::Tick);
+                }
+                _ = &mut exit => break,
+            }
+        }
+
+        log::info!("Shutting down, fading out");
+        app.process_audio_command(AudioCommand::FadeOutAll(SHUTDOWN_FADE));
+        tokio::time::sleep(SHUTDOWN_FADE).await;
+        Ok(())
+    })
+}
+
+// Resolve once the daemon is asked to exit, by SIGTERM from the service manager or Ctrl+C
+#[cfg(unix)]
+fn exit_signal() -> std::io::Result<impl Future<Output = ()>> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    Ok(async move {
+        tokio::select! {
+            _ = terminate.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    })
+}
+
+// Other platforms have no SIGTERM, Ctrl+C is the only signal there
+#[cfg(not(unix))]
+fn exit_signal() -> std::io::Result<impl Future<Output = ()>> {
+    Ok(async {
+        let _ = tokio::signal::ctrl_c().await;
+    })
+}
+
+// Resolve an optional background job, never completing when there is none
+async fn wait_for<T>(job: &mut Option<BoxFuture<'static, T>>) -> T {
+    match job {
+        Some(job) => job.await,
+        None => std::future::pending().await,
+    }
+}
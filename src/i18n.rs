@@ -34,6 +34,8 @@ pub static LANGUAGE_LOADER: LazyLock<FluentLanguageLoader> = LazyLock::new(|| {
     loader
         .load_fallback_language(&Localizations)
         .expect("Error while loading fallback language");
+    // Counts and names are shown inline in labels, without bidi isolation marks
+    loader.set_use_isolating(false);
 
     loader
 });
@@ -0,0 +1,428 @@
+//! Single-instance detection and the control socket protocol.
+//!
+//! The first instance binds a Unix domain socket at
+//! `$XDG_RUNTIME_DIR/cosmic-noise.sock`. Any later invocation connects to it,
+//! forwards its command and exits instead of opening a second window.
+//!
+//! The protocol is line based. A client writes one command per line and the
+//! running instance answers every line with `ok` or `err <reason>`:
+//!
+//! ```text
+//! toggle <track>            play or pause a track (index or name)
+//! volume <track> <0-100>    set the volume of a track in percent
+//! master <0-100>            set the master volume in percent
+//! pause                     pause all tracks
+//! resume                    resume all paused tracks
+//! stop                      stop all tracks
+//! preset <name>             apply a saved preset
+//! save-preset <name>        save the current mix as a preset
+//! sleep <minutes>           stop everything after the given time
+//! sleep off                 cancel the sleep timer
+//! show                      raise the window
+//! hide                      hide the window, playback continues
+//! quit                      exit the running instance
+//! ```
+//!
+//! The same words are accepted on the command line, e.g.
+//! `cosmic_noise volume rain 40`.
+//!
+//! Unix domain sockets only exist on Unix. Elsewhere there is no control
+//! socket, so every invocation runs its command in a window of its own.
+
+use std::fmt;
+use std::sync::mpsc::Sender;
+use std::sync::{LazyLock, Mutex};
+
+use iced::futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use iced::futures::stream::{self, BoxStream, StreamExt};
+
+use crate::models::NoiseTrack;
+
+// A track addressed either by its index in the track list or by its name
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackRef {
+    Index(usize),
+    Name(String),
+}
+
+impl TrackRef {
+    // Resolve the reference against the loaded track list
+    pub fn resolve(&self, tracks: &[NoiseTrack]) -> Option<usize> {
+        match self {
+            TrackRef::Index(index) => (*index < tracks.len()).then_some(*index),
+            TrackRef::Name(name) => tracks
+                .iter()
+                .position(|track| track.name.eq_ignore_ascii_case(name)),
+        }
+    }
+}
+
+impl From<&str> for TrackRef {
+    fn from(value: &str) -> Self {
+        match value.parse() {
+            Ok(index) => TrackRef::Index(index),
+            Err(_) => TrackRef::Name(value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for TrackRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackRef::Index(index) => write!(f, "{index}"),
+            TrackRef::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+// A command sent to the running instance
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Toggle(TrackRef),
+    SetVolume { track: TrackRef, percent: f32 },
+    SetMaster(f32),
+    PauseAll,
+    ResumeAll,
+    StopAll,
+    ApplyPreset(String),
+    SavePreset(String),
+    // Sleep timer in minutes, `None` cancels it
+    SleepTimer(Option<u64>),
+    Show,
+    Hide,
+    Quit,
+}
+
+impl Request {
+    // Whether the request has to wait for the track list to be loaded
+    pub fn needs_tracks(&self) -> bool {
+        !matches!(self, Request::Show | Request::Hide | Request::Quit)
+    }
+
+    // Parse a single protocol line
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (verb, rest) = match line.split_once(char::is_whitespace) {
+            Some((verb, rest)) => (verb, rest.trim()),
+            None => (line, ""),
+        };
+
+        match verb {
+            "toggle" => Ok(Request::Toggle(required(rest, "toggle <track>")?.into())),
+            "volume" => {
+                let (track, percent) = rest
+                    .rsplit_once(char::is_whitespace)
+                    .ok_or_else(|| "usage: volume <track> <0-100>".to_string())?;
+                Ok(Request::SetVolume {
+                    track: track.trim().into(),
+                    percent: parse_percent(percent)?,
+                })
+            }
+            "master" => Ok(Request::SetMaster(parse_percent(rest)?)),
+            "pause" => Ok(Request::PauseAll),
+            "resume" => Ok(Request::ResumeAll),
+            "stop" => Ok(Request::StopAll),
+            "preset" => Ok(Request::ApplyPreset(
+                required(rest, "preset <name>")?.to_string(),
+            )),
+            "save-preset" => Ok(Request::SavePreset(
+                required(rest, "save-preset <name>")?.to_string(),
+            )),
+            "sleep" => match rest {
+                "off" => Ok(Request::SleepTimer(None)),
+                minutes => minutes
+                    .parse()
+                    .map(|minutes| Request::SleepTimer(Some(minutes)))
+                    .map_err(|_| "usage: sleep <minutes>|off".to_string()),
+            },
+            "show" => Ok(Request::Show),
+            "hide" => Ok(Request::Hide),
+            "quit" => Ok(Request::Quit),
+            "" => Err("empty command".to_string()),
+            other => Err(format!("unknown command '{other}'")),
+        }
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Request::Toggle(track) => write!(f, "toggle {track}"),
+            Request::SetVolume { track, percent } => write!(f, "volume {track} {percent}"),
+            Request::SetMaster(percent) => write!(f, "master {percent}"),
+            Request::PauseAll => write!(f, "pause"),
+            Request::ResumeAll => write!(f, "resume"),
+            Request::StopAll => write!(f, "stop"),
+            Request::ApplyPreset(name) => write!(f, "preset {name}"),
+            Request::SavePreset(name) => write!(f, "save-preset {name}"),
+            Request::SleepTimer(Some(minutes)) => write!(f, "sleep {minutes}"),
+            Request::SleepTimer(None) => write!(f, "sleep off"),
+            Request::Show => write!(f, "show"),
+            Request::Hide => write!(f, "hide"),
+            Request::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+// Where the outcome of a request goes: `Ok`, or the reason it failed
+#[derive(Debug, Clone)]
+pub struct Reply(Sender<Result<(), String>>);
+
+impl Reply {
+    pub fn send(self, outcome: Result<(), String>) {
+        // The client may have given up waiting already
+        let _ = self.0.send(outcome);
+    }
+}
+
+// A request in the application inbox, with the client waiting for its outcome if any
+#[derive(Debug, Clone)]
+pub struct Incoming {
+    pub request: Request,
+    pub reply: Option<Reply>,
+}
+
+impl From<Request> for Incoming {
+    fn from(request: Request) -> Self {
+        Self {
+            request,
+            reply: None,
+        }
+    }
+}
+
+fn required<'a>(value: &'a str, usage: &str) -> Result<&'a str, String> {
+    if value.is_empty() {
+        Err(format!("usage: {usage}"))
+    } else {
+        Ok(value)
+    }
+}
+
+fn parse_percent(value: &str) -> Result<f32, String> {
+    match value.trim().parse::<f32>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(format!("'{value}' is not a percentage between 0 and 100")),
+    }
+}
+
+#[cfg(unix)]
+pub use socket::{Instance, Server, acquire, send};
+
+// The control socket itself, Unix domain sockets don't exist on other platforms so
+// every invocation runs on its own there
+#[cfg(unix)]
+mod socket {
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use super::{Incoming, Reply, Request};
+
+    const SOCKET_NAME: &str = "cosmic-noise.sock";
+
+    // How long a client waits for the running instance to answer
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+    // How long the server waits for the application to run a request, shorter than
+    // `REPLY_TIMEOUT` so the client still hears about it
+    const OUTCOME_TIMEOUT: Duration = Duration::from_secs(4);
+
+    // Location of the control socket
+    fn socket_path() -> PathBuf {
+        dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join(SOCKET_NAME)
+    }
+
+    // Outcome of trying to become the primary instance
+    pub enum Instance {
+        // No other instance is running, we own the socket now
+        Primary(Server),
+        // Another instance is already listening on the socket
+        Secondary,
+    }
+
+    // Try to bind the control socket, detecting an already running instance
+    pub fn acquire() -> io::Result<Instance> {
+        let path = socket_path();
+
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Ok(Instance::Secondary);
+            }
+            // Nobody is listening, the socket was left behind by a crashed instance
+            log::warn!("Removing stale control socket {}", path.display());
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        log::info!("Listening for commands on {}", path.display());
+        Ok(Instance::Primary(Server { listener }))
+    }
+
+    // Send a request to the running instance and return its reply
+    pub fn send(request: &Request) -> io::Result<String> {
+        let mut stream = UnixStream::connect(socket_path())?;
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        writeln!(stream, "{request}")?;
+
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        Ok(reply.trim().to_string())
+    }
+
+    // Listening side of the control socket
+    pub struct Server {
+        listener: UnixListener,
+    }
+
+    impl Server {
+        // Accept connections on a background thread, passing every parsed request to `handler`
+        // and answering with the outcome sent back through its reply
+        pub fn spawn<F>(self, handler: F)
+        where
+            F: Fn(Incoming) + Send + 'static,
+        {
+            std::thread::spawn(move || {
+                for stream in self.listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            if let Err(e) = handle_client(stream, &handler) {
+                                log::warn!("Control connection failed: {e}");
+                            }
+                        }
+                        Err(e) => log::warn!("Failed to accept control connection: {e}"),
+                    }
+                }
+            });
+        }
+    }
+
+    fn handle_client(stream: UnixStream, handler: &impl Fn(Incoming)) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let outcome = Request::parse(&line).and_then(|request| {
+                log::info!("Received command: {request}");
+                let (sender, outcome) = mpsc::channel();
+                handler(Incoming {
+                    request,
+                    reply: Some(Reply(sender)),
+                });
+                outcome
+                    .recv_timeout(OUTCOME_TIMEOUT)
+                    .unwrap_or_else(|_| Err("no answer from the running instance".to_string()))
+            });
+            match outcome {
+                Ok(()) => writeln!(writer, "ok")?,
+                Err(reason) => writeln!(writer, "err {reason}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Requests coming from outside the window (socket, command line) are queued here
+// until the application subscription picks them up
+static INBOX: LazyLock<(
+    UnboundedSender<Incoming>,
+    Mutex<Option<UnboundedReceiver<Incoming>>>,
+)> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::unbounded();
+    (sender, Mutex::new(Some(receiver)))
+});
+
+// Get a sender that feeds the application inbox
+pub fn sender() -> UnboundedSender<Incoming> {
+    INBOX.0.clone()
+}
+
+// Stream of queued requests, can only be taken once
+pub fn requests() -> BoxStream<'static, Incoming> {
+    match INBOX.1.lock().ok().and_then(|mut receiver| receiver.take()) {
+        Some(receiver) => receiver.boxed(),
+        None => stream::empty().boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_requests() {
+        assert_eq!(
+            Request::parse("toggle rain"),
+            Ok(Request::Toggle(TrackRef::Name("rain".to_string())))
+        );
+        assert_eq!(
+            Request::parse("toggle 3"),
+            Ok(Request::Toggle(TrackRef::Index(3)))
+        );
+        assert_eq!(
+            Request::parse("volume coffee shop 40"),
+            Ok(Request::SetVolume {
+                track: TrackRef::Name("coffee shop".to_string()),
+                percent: 40.0,
+            })
+        );
+        assert_eq!(Request::parse("  pause \n"), Ok(Request::PauseAll));
+        assert_eq!(
+            Request::parse("sleep 30"),
+            Ok(Request::SleepTimer(Some(30)))
+        );
+        assert_eq!(Request::parse("sleep off"), Ok(Request::SleepTimer(None)));
+
+        assert!(Request::parse("").is_err());
+        assert!(Request::parse("master 150").is_err());
+        assert!(Request::parse("volume rain").is_err());
+        assert!(Request::parse("preset").is_err());
+        assert!(Request::parse("dance").is_err());
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let requests = [
+            Request::Toggle(TrackRef::Name("rain".to_string())),
+            Request::SetVolume {
+                track: TrackRef::Index(2),
+                percent: 55.5,
+            },
+            Request::SetMaster(80.0),
+            Request::StopAll,
+            Request::ApplyPreset("Evening rain".to_string()),
+            Request::SavePreset("Focus".to_string()),
+            Request::SleepTimer(Some(45)),
+            Request::SleepTimer(None),
+            Request::Show,
+            Request::Hide,
+            Request::Quit,
+        ];
+
+        for request in requests {
+            assert_eq!(Request::parse(&request.to_string()), Ok(request));
+        }
+    }
+
+    #[test]
+    fn test_track_ref_resolve() {
+        let tracks = vec![
+            NoiseTrack::new("rain".to_string(), PathBuf::from("/test/rain.ogg")),
+            NoiseTrack::new("waves".to_string(), PathBuf::from("/test/waves.ogg")),
+        ];
+
+        assert_eq!(TrackRef::from("Waves").resolve(&tracks), Some(1));
+        assert_eq!(TrackRef::from("0").resolve(&tracks), Some(0));
+        assert_eq!(TrackRef::from("5").resolve(&tracks), None);
+        assert_eq!(TrackRef::from("birds").resolve(&tracks), None);
+    }
+}
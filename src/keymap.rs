@@ -0,0 +1,240 @@
+//! Keyboard shortcuts.
+//!
+//! Bindings are written as strings like `"space"`, `"ctrl+q"` or `"+"` in the
+//! `[keymap]` section of the configuration and compiled into a [`Keymap`]
+//! at startup. Digits always select the matching track card.
+
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+
+use crate::models::KeymapSettings;
+
+// Something a key press can trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    // Pause everything if something plays, resume otherwise
+    TogglePause,
+    // Move the card focus forward
+    SelectNext,
+    // Move the card focus backward
+    SelectPrevious,
+    // Play or pause the focused track
+    ToggleFocused,
+    // Raise the focused track, or the master volume if nothing is focused
+    VolumeUp,
+    // Lower the focused track, or the master volume if nothing is focused
+    VolumeDown,
+    // Leave the settings view or clear the focus
+    Back,
+    // Exit the application
+    Quit,
+    // Focus the card at this position in the (filtered) grid
+    Select(usize),
+}
+
+// Key part of a binding
+#[derive(Debug, Clone, PartialEq)]
+enum BindingKey {
+    Named(Named),
+    Character(String),
+}
+
+// A single parsed binding such as `ctrl+q`
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBinding {
+    key: BindingKey,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    logo: bool,
+}
+
+impl KeyBinding {
+    // Parse a binding string, e.g. `space`, `ctrl+q`, `+` or `ctrl++`
+    pub fn parse(binding: &str) -> Result<Self, String> {
+        let binding = binding.trim().to_lowercase();
+        let (modifiers, key) = if binding == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = binding.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            binding.rsplit_once('+').unwrap_or(("", binding.as_str()))
+        };
+
+        let mut parsed = KeyBinding {
+            key: parse_key(key).ok_or_else(|| format!("unknown key '{key}' in '{binding}'"))?,
+            ctrl: false,
+            alt: false,
+            shift: false,
+            logo: false,
+        };
+
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier {
+                "ctrl" | "control" => parsed.ctrl = true,
+                "alt" => parsed.alt = true,
+                "shift" => parsed.shift = true,
+                "super" | "logo" | "meta" => parsed.logo = true,
+                other => return Err(format!("unknown modifier '{other}' in '{binding}'")),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    // Whether a key press matches this binding. Shift is only checked when the
+    // binding asks for it, since symbols like `+` need shift on many layouts.
+    pub fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        let key_matches = match (&self.key, key) {
+            (BindingKey::Named(expected), Key::Named(named)) => expected == named,
+            (BindingKey::Character(expected), Key::Character(c)) => {
+                expected.as_str() == c.to_lowercase()
+            }
+            _ => false,
+        };
+
+        key_matches
+            && self.ctrl == modifiers.control()
+            && self.alt == modifiers.alt()
+            && self.logo == modifiers.logo()
+            && (!self.shift || modifiers.shift())
+    }
+}
+
+fn parse_key(key: &str) -> Option<BindingKey> {
+    let named = match key {
+        "space" => Named::Space,
+        "enter" | "return" => Named::Enter,
+        "escape" | "esc" => Named::Escape,
+        "tab" => Named::Tab,
+        "backspace" => Named::Backspace,
+        "delete" | "del" => Named::Delete,
+        "left" => Named::ArrowLeft,
+        "right" => Named::ArrowRight,
+        "up" => Named::ArrowUp,
+        "down" => Named::ArrowDown,
+        "home" => Named::Home,
+        "end" => Named::End,
+        "pageup" => Named::PageUp,
+        "pagedown" => Named::PageDown,
+        other if other.chars().count() == 1 => {
+            return Some(BindingKey::Character(other.to_string()));
+        }
+        _ => return None,
+    };
+
+    Some(BindingKey::Named(named))
+}
+
+// Compiled keyboard shortcuts
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Keymap {
+    // Compile the configured bindings, skipping the ones that don't parse
+    pub fn new(settings: &KeymapSettings) -> Self {
+        let actions = [
+            (&settings.toggle_pause, Action::TogglePause),
+            (&settings.select_next, Action::SelectNext),
+            (&settings.select_previous, Action::SelectPrevious),
+            (&settings.toggle_focused, Action::ToggleFocused),
+            (&settings.volume_up, Action::VolumeUp),
+            (&settings.volume_down, Action::VolumeDown),
+            (&settings.back, Action::Back),
+            (&settings.quit, Action::Quit),
+        ];
+
+        let mut bindings = Vec::new();
+        for (keys, action) in actions {
+            for key in keys {
+                match KeyBinding::parse(key) {
+                    Ok(binding) => bindings.push((binding, action)),
+                    Err(e) => log::warn!("Ignoring key binding for {action:?}: {e}"),
+                }
+            }
+        }
+
+        Self { bindings }
+    }
+
+    // Find the action bound to a key press
+    pub fn action(&self, key: &Key, modifiers: Modifiers) -> Option<Action> {
+        if let Some(action) = self
+            .bindings
+            .iter()
+            .find(|(binding, _)| binding.matches(key, modifiers))
+            .map(|(_, action)| *action)
+        {
+            return Some(action);
+        }
+
+        // Digits 1-9 select the first nine cards, 0 the tenth
+        match key {
+            Key::Character(c) if !modifiers.control() && !modifiers.alt() => {
+                let digit = c.parse::<usize>().ok().filter(|digit| *digit <= 9)?;
+                Some(Action::Select(if digit == 0 { 9 } else { digit - 1 }))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character(c: &str) -> Key {
+        Key::Character(c.into())
+    }
+
+    #[test]
+    fn test_parse_bindings() {
+        assert!(KeyBinding::parse("space").is_ok());
+        assert!(KeyBinding::parse("Ctrl+Q").is_ok());
+        assert!(KeyBinding::parse("+").is_ok());
+        assert!(KeyBinding::parse("ctrl++").is_ok());
+        assert!(KeyBinding::parse("hyper+q").is_err());
+        assert!(KeyBinding::parse("spacebar").is_err());
+    }
+
+    #[test]
+    fn test_binding_matches() {
+        let quit = KeyBinding::parse("ctrl+q").unwrap();
+        assert!(quit.matches(&character("q"), Modifiers::CTRL));
+        assert!(quit.matches(&character("Q"), Modifiers::CTRL | Modifiers::SHIFT));
+        assert!(!quit.matches(&character("q"), Modifiers::empty()));
+
+        let plus = KeyBinding::parse("+").unwrap();
+        assert!(plus.matches(&character("+"), Modifiers::SHIFT));
+        assert!(!plus.matches(&character("+"), Modifiers::CTRL));
+    }
+
+    #[test]
+    fn test_default_keymap() {
+        let keymap = Keymap::new(&KeymapSettings::default());
+
+        assert_eq!(
+            keymap.action(&Key::Named(Named::Space), Modifiers::empty()),
+            Some(Action::TogglePause)
+        );
+        assert_eq!(
+            keymap.action(&Key::Named(Named::ArrowRight), Modifiers::empty()),
+            Some(Action::SelectNext)
+        );
+        assert_eq!(
+            keymap.action(&character("q"), Modifiers::CTRL),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keymap.action(&character("3"), Modifiers::empty()),
+            Some(Action::Select(2))
+        );
+        assert_eq!(
+            keymap.action(&character("0"), Modifiers::empty()),
+            Some(Action::Select(9))
+        );
+        assert_eq!(keymap.action(&character("x"), Modifiers::empty()), None);
+    }
+}
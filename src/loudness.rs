@@ -0,0 +1,193 @@
+//! Loudness analysis used to normalize tracks against each other.
+//!
+//! Every file is decoded once on a blocking thread and its RMS level is
+//! measured. Results are cached in `$XDG_CACHE_HOME/cosmic-noise/loudness.toml`
+//! keyed by path and modification time, so only new or changed files are
+//! decoded again.
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use iced::futures::future::{BoxFuture, FutureExt};
+use kira::Frame;
+use kira::sound::static_sound::StaticSoundData;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigManager;
+
+// Level every track is brought to, in dBFS RMS
+pub const TARGET_LOUDNESS_DB: f32 = -20.0;
+// Largest correction applied to a single track
+pub const MAX_GAIN_OFFSET_DB: f32 = 12.0;
+
+const CACHE_FILE: &str = "cosmic-noise/loudness.toml";
+
+// Gain that brings a track measured at `loudness` to the target level
+pub fn gain_offset(loudness: f32) -> f32 {
+    (TARGET_LOUDNESS_DB - loudness).clamp(-MAX_GAIN_OFFSET_DB, MAX_GAIN_OFFSET_DB)
+}
+
+// RMS level of both channels in dBFS, `None` for silence
+pub fn rms_db(frames: &[Frame]) -> Option<f32> {
+    if frames.is_empty() {
+        return None;
+    }
+
+    let sum: f64 = frames
+        .iter()
+        .map(|frame| (frame.left as f64).powi(2) + (frame.right as f64).powi(2))
+        .sum();
+    let mean = sum / (frames.len() * 2) as f64;
+
+    (mean > 0.0).then(|| (10.0 * mean.log10()) as f32)
+}
+
+// Decode a file and measure its level
+fn measure(path: &Path) -> Option<f32> {
+    let data = StaticSoundData::from_file(path)
+        .inspect_err(|e| log::warn!("Cannot analyze {}: {e}", path.display()))
+        .ok()?;
+    rms_db(&data.frames)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheEntry {
+    path: PathBuf,
+    // Modification time in seconds since the epoch
+    modified: u64,
+    // Measured level in dBFS
+    loudness: f32,
+}
+
+// Measurements from previous runs
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+struct LoudnessCache {
+    #[serde(default)]
+    entries: Vec<CacheEntry>,
+}
+
+impl LoudnessCache {
+    fn path() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join(CACHE_FILE))
+    }
+
+    fn load() -> Self {
+        Self::path()
+            .map(|path| ConfigManager::load_toml(&path))
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Err(e) = ConfigManager::save_toml(&path, self) {
+            log::warn!("Failed to save loudness cache: {e}");
+        }
+    }
+
+    // Cached level of a file, if it hasn't changed since it was measured
+    fn get(&self, path: &Path, modified: u64) -> Option<f32> {
+        self.entries
+            .iter()
+            .find(|entry| entry.path == path && entry.modified == modified)
+            .map(|entry| entry.loudness)
+    }
+
+    fn insert(&mut self, path: &Path, modified: u64, loudness: f32) {
+        self.entries.retain(|entry| entry.path != path);
+        self.entries.push(CacheEntry {
+            path: path.to_path_buf(),
+            modified,
+            loudness,
+        });
+    }
+}
+
+fn modified(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+// Measure the given tracks on a blocking thread, returning `(track index, level)` pairs
+pub fn analyze(tracks: Vec<(usize, PathBuf)>) -> BoxFuture<'static, Vec<(usize, f32)>> {
+    async move {
+        tokio::task::spawn_blocking(move || {
+            let mut cache = LoudnessCache::load();
+            let mut changed = false;
+            let mut measured = Vec::new();
+
+            for (track_id, path) in tracks {
+                let Some(modified) = modified(&path) else {
+                    continue;
+                };
+                let loudness = match cache.get(&path, modified) {
+                    Some(loudness) => Some(loudness),
+                    None => {
+                        let loudness = measure(&path);
+                        if let Some(loudness) = loudness {
+                            log::info!("Measured {}: {loudness:.1} dBFS", path.display());
+                            cache.insert(&path, modified, loudness);
+                            changed = true;
+                        }
+                        loudness
+                    }
+                };
+                if let Some(loudness) = loudness {
+                    measured.push((track_id, loudness));
+                }
+            }
+
+            if changed {
+                cache.save();
+            }
+            measured
+        })
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("Loudness analysis failed: {e}");
+            vec![]
+        })
+    }
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gain_offset() {
+        assert_eq!(gain_offset(TARGET_LOUDNESS_DB), 0.0);
+        assert_eq!(gain_offset(-26.0), 6.0);
+        assert_eq!(gain_offset(-60.0), MAX_GAIN_OFFSET_DB);
+        assert_eq!(gain_offset(0.0), -MAX_GAIN_OFFSET_DB);
+    }
+
+    #[test]
+    fn test_rms_db() {
+        assert_eq!(rms_db(&[]), None);
+        assert_eq!(rms_db(&[Frame::ZERO; 4]), None);
+
+        // Full scale square wave is 0 dBFS, half amplitude is about -6 dBFS
+        let full = [Frame::new(1.0, -1.0), Frame::new(-1.0, 1.0)];
+        assert!(rms_db(&full).unwrap().abs() < 1e-3);
+        let half = [Frame::new(0.5, -0.5), Frame::new(-0.5, 0.5)];
+        assert!((rms_db(&half).unwrap() + 6.02).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_cache_lookup() {
+        let mut cache = LoudnessCache::default();
+        let path = Path::new("/test/rain.ogg");
+        cache.insert(path, 100, -18.0);
+
+        assert_eq!(cache.get(path, 100), Some(-18.0));
+        // A modified file has to be measured again
+        assert_eq!(cache.get(path, 200), None);
+
+        cache.insert(path, 200, -22.0);
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.get(path, 200), Some(-22.0));
+    }
+}
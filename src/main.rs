@@ -1,11 +1,29 @@
 mod app;
 mod audio;
+mod ceiling;
+mod cli;
 mod config;
+mod drift;
 mod errors;
+mod events;
+mod export;
+mod focus;
+mod generator;
+mod granular;
+mod headless;
 mod i18n;
+mod ipc;
+mod keymap;
+mod loudness;
 mod models;
+mod mpris;
+mod pcm;
+mod source;
+mod synth;
+mod tray;
 mod ui;
 mod utils;
+mod variations;
 use iced::{Color, Size, Theme, theme, window};
 
 use crate::app::{CosmicNoise, Message};
@@ -27,30 +45,79 @@ pub fn main() -> iced::Result {
     // Enable localizations to be applied.
     i18n::init(&requested_languages);
 
-    iced::application(CosmicNoise::new, CosmicNoise::update, CosmicNoise::view)
-        .font(include_bytes!("../assets/fonts/dragwin.ttf").as_slice())
-        .window(window::Settings {
-            transparent: true,
-            decorations: false,
-            size: Size::new(800., 650.),
-            min_size: Some(Size::new(550., 350.)),
-            visible: true,
-
-            ..Default::default()
-        })
-        .theme(|app: &CosmicNoise| match app.current_theme {
-            AppTheme::Light => Theme::Light,
-            AppTheme::GruvboxDark => Theme::GruvboxDark,
-            AppTheme::Tokyo => Theme::TokyoNight,
-            AppTheme::Catppuccin => Theme::CatppuccinMacchiato,
-            AppTheme::GruvboxLight => Theme::GruvboxLight,
-            AppTheme::Moonfly => Theme::Moonfly,
-        })
-        .style(|_, _| theme::Style {
-            background_color: Color::TRANSPARENT,
-            text_color: Color::WHITE,
-        })
-        .run()
+    // Anything besides flags is a control command, e.g. `cosmic_noise toggle rain`
+    let options = match cli::Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(reason) => {
+            eprintln!("cosmic_noise: {reason}");
+            std::process::exit(2);
+        }
+    };
+    let request = options.request;
+
+    #[cfg(unix)]
+    match ipc::acquire() {
+        Ok(ipc::Instance::Primary(server)) => {
+            let inbox = ipc::sender();
+            server.spawn(move |incoming| {
+                let _ = inbox.unbounded_send(incoming);
+            });
+        }
+        Ok(ipc::Instance::Secondary) => {
+            // Forward to the running instance instead of opening a second window
+            let request = request.unwrap_or(ipc::Request::Show);
+            match ipc::send(&request) {
+                Ok(reply) if reply == "ok" => return Ok(()),
+                Ok(reply) => eprintln!("cosmic_noise: {reply}"),
+                Err(e) => eprintln!("cosmic_noise: failed to reach running instance: {e}"),
+            }
+            std::process::exit(1);
+        }
+        Err(e) => log::warn!("Single-instance detection unavailable: {e}"),
+    }
+
+    // Commands given to the first instance run once the tracks are loaded
+    if let Some(request) = request {
+        let _ = ipc::sender().unbounded_send(request.into());
+    }
+
+    if options.headless {
+        if let Err(e) = headless::run(options.pcm_output) {
+            eprintln!("cosmic_noise: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    iced::application(
+        move || CosmicNoise::new(options.pcm_output.clone()),
+        CosmicNoise::update,
+        CosmicNoise::view,
+    )
+    .subscription(CosmicNoise::subscription)
+    .font(include_bytes!("../assets/fonts/dragwin.ttf").as_slice())
+    .window(window::Settings {
+        transparent: true,
+        decorations: false,
+        size: Size::new(800., 650.),
+        min_size: Some(Size::new(550., 350.)),
+        visible: true,
+
+        ..Default::default()
+    })
+    .theme(|app: &CosmicNoise| match app.current_theme {
+        AppTheme::Light => Theme::Light,
+        AppTheme::GruvboxDark => Theme::GruvboxDark,
+        AppTheme::Tokyo => Theme::TokyoNight,
+        AppTheme::Catppuccin => Theme::CatppuccinMacchiato,
+        AppTheme::GruvboxLight => Theme::GruvboxLight,
+        AppTheme::Moonfly => Theme::Moonfly,
+    })
+    .style(|_, _| theme::Style {
+        background_color: Color::TRANSPARENT,
+        text_color: Color::WHITE,
+    })
+    .run()
 }
 impl CosmicNoise {
     fn view(&self) -> iced::Element<Message> {
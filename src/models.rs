@@ -1,6 +1,10 @@
+use crate::fl;
+use crate::pcm::PcmOutput;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Timelike, Weekday};
 use kira::sound::PlaybackState;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 // Core domain model representing an audio track
@@ -8,29 +12,511 @@ use std::time::Duration;
 pub struct NoiseTrack {
     // Display name of the track (usually filename without extension)
     pub name: String,
-    // Full file path to the audio file
-    pub path: PathBuf,
+    // What the track plays: a file, a folder of clips or a synthesized tone
+    pub source: SoundSource,
     // Current volume level in decibels (-60.0 to 0.0)
     pub volume_level: f32,
     // Current playback state
     pub state: PlaybackState,
     // Track metadata (optional)
     pub metadata: Option<TrackMetadata>,
+    // Category the track is grouped under (Nature, Urban, Noise...)
+    pub category: Option<String>,
+    // Free-form tags used when filtering
+    pub tags: Vec<String>,
+    // Marked as favorite by the user
+    pub favorite: bool,
+    // Silenced without losing volume or position
+    pub muted: bool,
+    // Soloed tracks silence every track that isn't soloed
+    pub solo: bool,
+    // Stereo position from -1.0 (left) to 1.0 (right), 0.0 is centred
+    pub pan: f32,
+    // Playback speed, 1.0 is the recorded speed and the pitch follows
+    pub playback_rate: f32,
+    // Filter and EQ settings
+    pub tone: ToneSettings,
+    // Slow random swell of the volume
+    pub drift: DriftSettings,
+    // Measured RMS level in dBFS, used when normalization is on
+    pub loudness: Option<f32>,
 }
 
 impl NoiseTrack {
-    // Create a new noise track with default settings
+    // Create a new noise track looping the file at `path`, with default settings
     pub fn new(name: String, path: PathBuf) -> Self {
+        Self::with_source(name, SoundSource::File(path))
+    }
+
+    // Create a new track playing any kind of source, with default settings
+    pub fn with_source(name: String, source: SoundSource) -> Self {
         Self {
             name,
-            path,
+            source,
             volume_level: DEFAULT_VOLUME_DB,
             state: PlaybackState::Stopped,
             metadata: None,
+            category: None,
+            tags: vec![],
+            favorite: false,
+            muted: false,
+            solo: false,
+            pan: 0.0,
+            playback_rate: 1.0,
+            tone: ToneSettings::default(),
+            drift: DriftSettings::default(),
+            loudness: None,
+        }
+    }
+
+    // Built-in tone generator track, without a file
+    pub fn generator(settings: GeneratorSettings) -> Self {
+        Self {
+            category: Some(GENERATOR_CATEGORY.to_string()),
+            ..Self::with_source(
+                GENERATOR_TRACK.to_string(),
+                SoundSource::Generator(settings),
+            )
+        }
+    }
+}
+
+// What a track plays, with the parameters of that kind of sound
+#[derive(Debug, Clone, PartialEq)]
+pub enum SoundSource {
+    // An audio file, looped
+    File(PathBuf),
+    // An audio file cut into an endless granular texture
+    Granular(PathBuf, GranularSettings),
+    // A folder of one-shot clips fired at random intervals
+    Events(PathBuf, EventSettings),
+    // A folder of takes of the same sound, rotated with crossfades
+    Variations(PathBuf, VariationSettings),
+    // A synthesized binaural beat, isochronic tone or colored noise
+    Generator(GeneratorSettings),
+}
+
+impl SoundSource {
+    // Single audio file the source reads, whose loudness can be measured
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            SoundSource::File(path) | SoundSource::Granular(path, _) => Some(path),
+            SoundSource::Events(..) | SoundSource::Variations(..) | SoundSource::Generator(_) => {
+                None
+            }
+        }
+    }
+
+    pub fn generator(&self) -> Option<GeneratorSettings> {
+        match self {
+            SoundSource::Generator(settings) => Some(*settings),
+            _ => None,
+        }
+    }
+
+    pub fn granular(&self) -> Option<GranularSettings> {
+        match self {
+            SoundSource::Granular(_, settings) => Some(*settings),
+            _ => None,
+        }
+    }
+
+    // Loop the file, or play it as a granular texture; other sources stay as they are
+    pub fn with_granular(self, granular: Option<GranularSettings>) -> Self {
+        match (self, granular) {
+            (SoundSource::File(path) | SoundSource::Granular(path, _), Some(settings)) => {
+                SoundSource::Granular(path, settings)
+            }
+            (SoundSource::File(path) | SoundSource::Granular(path, _), None) => {
+                SoundSource::File(path)
+            }
+            (source, _) => source,
         }
     }
 }
 
+// Filter applied to the track grid
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TrackFilter {
+    // Text matched against track name, category and tags
+    pub query: String,
+    // Only show tracks that are playing
+    pub playing_only: bool,
+    // Only show favorite tracks
+    pub favorites_only: bool,
+}
+
+impl TrackFilter {
+    // Check whether a track passes the filter
+    pub fn matches(&self, track: &NoiseTrack) -> bool {
+        if self.playing_only && !matches!(track.state, PlaybackState::Playing) {
+            return false;
+        }
+        if self.favorites_only && !track.favorite {
+            return false;
+        }
+
+        let query = self.query.trim().to_lowercase();
+        query.is_empty()
+            || track.name.to_lowercase().contains(&query)
+            || track
+                .category
+                .as_ref()
+                .is_some_and(|category| category.to_lowercase().contains(&query))
+            || track
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&query))
+    }
+
+    // Whether any criteria are set
+    pub fn is_active(&self) -> bool {
+        !self.query.trim().is_empty() || self.playing_only || self.favorites_only
+    }
+}
+
+// Per-track tone shaping: low-pass and high-pass filters plus a three-band EQ
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToneSettings {
+    // Low-pass cutoff in Hz, `MAX_CUTOFF_HZ` leaves the track untouched
+    pub low_pass: f32,
+    // High-pass cutoff in Hz, `MIN_CUTOFF_HZ` leaves the track untouched
+    pub high_pass: f32,
+    // Low shelf gain in decibels
+    pub low: f32,
+    // Mid bell gain in decibels
+    pub mid: f32,
+    // High shelf gain in decibels
+    pub high: f32,
+}
+
+impl Default for ToneSettings {
+    fn default() -> Self {
+        Self {
+            low_pass: MAX_CUTOFF_HZ,
+            high_pass: MIN_CUTOFF_HZ,
+            low: 0.0,
+            mid: 0.0,
+            high: 0.0,
+        }
+    }
+}
+
+impl ToneSettings {
+    // Whether any filter or EQ band is engaged
+    pub fn is_flat(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// How the generator makes the beat audible, or plain noise without a tone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GeneratorMode {
+    // A slightly different tone in each ear, needs headphones
+    #[default]
+    Binaural,
+    // One tone pulsing on and off, works on speakers too
+    Isochronic,
+    // Noise of the chosen color, a different one in each ear
+    Noise,
+}
+
+impl GeneratorMode {
+    pub fn all() -> &'static [GeneratorMode] {
+        &[
+            GeneratorMode::Binaural,
+            GeneratorMode::Isochronic,
+            GeneratorMode::Noise,
+        ]
+    }
+
+    // Whether the mode plays a tone, with the carrier and beat controls
+    pub fn is_tone(self) -> bool {
+        self != GeneratorMode::Noise
+    }
+}
+
+impl std::fmt::Display for GeneratorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorMode::Binaural => write!(f, "{}", fl!("generator-binaural")),
+            GeneratorMode::Isochronic => write!(f, "{}", fl!("generator-isochronic")),
+            GeneratorMode::Noise => write!(f, "{}", fl!("generator-noise")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GeneratorWaveform {
+    #[default]
+    Sine,
+    Triangle,
+    Square,
+}
+
+impl GeneratorWaveform {
+    pub fn all() -> &'static [GeneratorWaveform] {
+        &[
+            GeneratorWaveform::Sine,
+            GeneratorWaveform::Triangle,
+            GeneratorWaveform::Square,
+        ]
+    }
+}
+
+impl std::fmt::Display for GeneratorWaveform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorWaveform::Sine => write!(f, "{}", fl!("waveform-sine")),
+            GeneratorWaveform::Triangle => write!(f, "{}", fl!("waveform-triangle")),
+            GeneratorWaveform::Square => write!(f, "{}", fl!("waveform-square")),
+        }
+    }
+}
+
+// Spectrum of the generator's noise mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NoiseColor {
+    // Equal energy at every frequency, a bright hiss
+    White,
+    // Falling 3 dB per octave, like steady rain
+    #[default]
+    Pink,
+    // Falling 6 dB per octave, a deep rumble
+    Brown,
+}
+
+impl NoiseColor {
+    pub fn all() -> &'static [NoiseColor] {
+        &[NoiseColor::White, NoiseColor::Pink, NoiseColor::Brown]
+    }
+}
+
+impl std::fmt::Display for NoiseColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoiseColor::White => write!(f, "{}", fl!("noise-white")),
+            NoiseColor::Pink => write!(f, "{}", fl!("noise-pink")),
+            NoiseColor::Brown => write!(f, "{}", fl!("noise-brown")),
+        }
+    }
+}
+
+// Brainwave ranges offered as beat frequency presets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrainwaveBand {
+    // Deep sleep
+    Delta,
+    // Meditation, drowsiness
+    Theta,
+    // Relaxed focus
+    Alpha,
+    // Active concentration
+    Beta,
+}
+
+impl BrainwaveBand {
+    pub fn all() -> &'static [BrainwaveBand] {
+        &[
+            BrainwaveBand::Delta,
+            BrainwaveBand::Theta,
+            BrainwaveBand::Alpha,
+            BrainwaveBand::Beta,
+        ]
+    }
+
+    // Beat frequency in Hz, in the middle of the band
+    pub fn beat(self) -> f32 {
+        match self {
+            BrainwaveBand::Delta => 2.0,
+            BrainwaveBand::Theta => 6.0,
+            BrainwaveBand::Alpha => 10.0,
+            BrainwaveBand::Beta => 18.0,
+        }
+    }
+
+    // Band a beat frequency falls in
+    pub fn of(beat: f32) -> Self {
+        match beat {
+            beat if beat < 4.0 => BrainwaveBand::Delta,
+            beat if beat < 8.0 => BrainwaveBand::Theta,
+            beat if beat < 13.0 => BrainwaveBand::Alpha,
+            _ => BrainwaveBand::Beta,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BrainwaveBand::Delta => "δ",
+            BrainwaveBand::Theta => "θ",
+            BrainwaveBand::Alpha => "α",
+            BrainwaveBand::Beta => "β",
+        }
+    }
+}
+
+impl std::fmt::Display for BrainwaveBand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BrainwaveBand::Delta => write!(f, "{}", fl!("band-delta")),
+            BrainwaveBand::Theta => write!(f, "{}", fl!("band-theta")),
+            BrainwaveBand::Alpha => write!(f, "{}", fl!("band-alpha")),
+            BrainwaveBand::Beta => write!(f, "{}", fl!("band-beta")),
+        }
+    }
+}
+
+// Tone of the binaural beat and isochronic pulse generator, or its noise
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorSettings {
+    pub mode: GeneratorMode,
+    // Frequency of the tone in Hz
+    pub carrier: f32,
+    // Difference between the ears, or pulses per second, in Hz
+    pub beat: f32,
+    pub waveform: GeneratorWaveform,
+    // Color played in noise mode
+    pub noise: NoiseColor,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self {
+            mode: GeneratorMode::default(),
+            carrier: 200.0,
+            beat: BrainwaveBand::Alpha.beat(),
+            waveform: GeneratorWaveform::default(),
+            noise: NoiseColor::default(),
+        }
+    }
+}
+
+// Envelope shaping each grain of a granular texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GrainWindow {
+    // Smooth bell, the softest blend
+    #[default]
+    Hann,
+    // Linear rise and fall
+    Triangle,
+    // Flat top with short fades, grains keep more of their attack
+    Tukey,
+}
+
+impl GrainWindow {
+    pub fn all() -> &'static [GrainWindow] {
+        &[GrainWindow::Hann, GrainWindow::Triangle, GrainWindow::Tukey]
+    }
+}
+
+impl std::fmt::Display for GrainWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrainWindow::Hann => write!(f, "{}", fl!("window-hann")),
+            GrainWindow::Triangle => write!(f, "{}", fl!("window-triangle")),
+            GrainWindow::Tukey => write!(f, "{}", fl!("window-tukey")),
+        }
+    }
+}
+
+// Parameters of a granular texture made from a track's file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GranularSettings {
+    // Length of each grain in milliseconds
+    pub grain_size: f32,
+    // Grains started per second
+    pub density: f32,
+    // How far from the playhead grains are read, as a share of the file
+    pub position_jitter: f32,
+    // Largest random detune of a grain in semitones
+    pub pitch_jitter: f32,
+    pub window: GrainWindow,
+}
+
+impl Default for GranularSettings {
+    fn default() -> Self {
+        Self {
+            grain_size: 120.0,
+            density: 20.0,
+            position_jitter: 0.2,
+            pitch_jitter: 0.0,
+            window: GrainWindow::default(),
+        }
+    }
+}
+
+// Curve the volume drift follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DriftShape {
+    // Smoothed random noise, swelling by different amounts each time
+    #[default]
+    Random,
+    // Regular sine wave
+    Wave,
+}
+
+impl DriftShape {
+    pub fn toggled(self) -> Self {
+        match self {
+            DriftShape::Random => DriftShape::Wave,
+            DriftShape::Wave => DriftShape::Random,
+        }
+    }
+}
+
+impl std::fmt::Display for DriftShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DriftShape::Random => write!(f, "{}", fl!("drift-random")),
+            DriftShape::Wave => write!(f, "{}", fl!("drift-wave")),
+        }
+    }
+}
+
+// Slow variation of a track's volume around its set level ("breathing")
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriftSettings {
+    // Largest deviation from the set volume in decibels, 0.0 turns drift off
+    pub depth: f32,
+    // Swells per minute
+    pub rate: f32,
+    pub shape: DriftShape,
+}
+
+impl Default for DriftSettings {
+    fn default() -> Self {
+        Self {
+            depth: 0.0,
+            rate: 4.0,
+            shape: DriftShape::default(),
+        }
+    }
+}
+
+impl DriftSettings {
+    pub fn is_off(&self) -> bool {
+        self.depth <= 0.0
+    }
+
+    // Length of one swell
+    pub fn period(&self) -> Duration {
+        Duration::from_secs_f32(60.0 / self.rate.clamp(MIN_DRIFT_RATE, MAX_DRIFT_RATE))
+    }
+
+    // Lowest and highest volume in dB reached around `volume`
+    pub fn range(&self, volume: f32) -> (f32, f32) {
+        let depth = self.depth.max(0.0);
+        (
+            (volume - depth).max(MIN_VOLUME_DB),
+            (volume + depth).min(MAX_VOLUME_DB),
+        )
+    }
+}
+
 // Optional metadata for audio tracks
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct TrackMetadata {
@@ -50,6 +536,143 @@ pub struct TrackMetadata {
     pub last_modified: Option<std::time::SystemTime>,
 }
 
+// Contents of the optional `<track>.toml` file placed next to an audio file
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackSidecar {
+    // Category, overrides the subdirectory name
+    pub category: Option<String>,
+    // Tags used when filtering
+    pub tags: Vec<String>,
+}
+
+// How often and how varied the clips of an event track are played
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventSettings {
+    // Average number of clips per minute
+    pub rate: f32,
+    // Random deviation of the interval between clips, 0.0 (regular) to 1.0 (±100%)
+    pub jitter: f32,
+    // Clips are played up to this many dB quieter than the track volume
+    pub volume_variance: f32,
+    // Clips are placed up to this far left or right of the track pan, 0.0 to 1.0
+    pub pan_spread: f32,
+}
+
+impl Default for EventSettings {
+    fn default() -> Self {
+        Self {
+            rate: 2.0,
+            jitter: 0.5,
+            volume_variance: 6.0,
+            pan_spread: 0.5,
+        }
+    }
+}
+
+// How a variation track moves from one file to the next
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VariationSettings {
+    // Overlap of two variations, at most half of the shorter one
+    pub crossfade_seconds: u64,
+}
+
+impl Default for VariationSettings {
+    fn default() -> Self {
+        Self {
+            crossfade_seconds: 5,
+        }
+    }
+}
+
+impl VariationSettings {
+    pub fn crossfade(&self) -> Duration {
+        Duration::from_secs(self.crossfade_seconds)
+    }
+}
+
+// Contents of the `variations.toml` file of a variation track folder
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VariationSidecar {
+    // Category, overrides the parent directory name
+    pub category: Option<String>,
+    // Tags used when filtering
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    pub settings: VariationSettings,
+}
+
+// Contents of the `event.toml` file of an event track folder
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventSidecar {
+    // Category, overrides the parent directory name
+    pub category: Option<String>,
+    // Tags used when filtering
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    pub settings: EventSettings,
+}
+
+// A group of tracks sharing a category, as shown in the player view
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackSection {
+    // Category of the tracks, `None` for uncategorized ones
+    pub category: Option<String>,
+    // Indices into the track list
+    pub tracks: Vec<usize>,
+}
+
+impl TrackSection {
+    // Group tracks by category. Sections are sorted by name with the
+    // uncategorized tracks last, tracks keep their list order.
+    pub fn group(tracks: &[NoiseTrack], indices: impl IntoIterator<Item = usize>) -> Vec<Self> {
+        let mut sections: Vec<TrackSection> = Vec::new();
+        for index in indices {
+            let category = &tracks[index].category;
+            match sections
+                .iter_mut()
+                .find(|section| section.category == *category)
+            {
+                Some(section) => section.tracks.push(index),
+                None => sections.push(TrackSection {
+                    category: category.clone(),
+                    tracks: vec![index],
+                }),
+            }
+        }
+
+        sections.sort_by(|a, b| match (&a.category, &b.category) {
+            (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        sections
+    }
+
+    // Name shown in the header and used to remember the collapsed state
+    pub fn name(&self) -> &str {
+        self.category.as_deref().unwrap_or(UNCATEGORIZED)
+    }
+
+    // Whether headers are worth showing, i.e. at least one track has a category
+    pub fn is_grouped(sections: &[TrackSection]) -> bool {
+        sections.iter().any(|section| section.category.is_some())
+    }
+
+    // Number of playing tracks in the section
+    pub fn playing_count(&self, tracks: &[NoiseTrack]) -> usize {
+        self.tracks
+            .iter()
+            .filter(|index| matches!(tracks[**index].state, PlaybackState::Playing))
+            .count()
+    }
+}
+
 // Audio system configuration settings
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AudioSettings {
@@ -63,10 +686,19 @@ pub struct AudioSettings {
     pub buffer_size: Option<u32>,
     // Maximum number of simultaneous tracks
     pub max_concurrent_tracks: usize,
+    // What happens when a track is started while the limit is reached
+    #[serde(default)]
+    pub concurrency_policy: ConcurrencyPolicy,
     // Enable audio normalization
     pub normalize_audio: bool,
     // Master volume level
     pub master_volume: f32,
+    // Limiter on the master bus
+    #[serde(default)]
+    pub limiter: LimiterSettings,
+    // Raw PCM sink given on the command line, never saved
+    #[serde(skip)]
+    pub pcm_output: Option<PcmOutput>,
 }
 
 impl Default for AudioSettings {
@@ -77,8 +709,55 @@ impl Default for AudioSettings {
             loop_region: Some(0.0..),
             buffer_size: None,
             max_concurrent_tracks: 16,
+            concurrency_policy: ConcurrencyPolicy::default(),
             normalize_audio: false,
             master_volume: DEFAULT_VOLUME_DB, // Start at 50% like other sliders
+            limiter: LimiterSettings::default(),
+            pcm_output: None,
+        }
+    }
+}
+
+// Behaviour when starting a track would exceed `max_concurrent_tracks`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ConcurrencyPolicy {
+    // Don't start the track and show a notice
+    #[default]
+    Refuse,
+    // Fade out the track that was started first to make room
+    StopOldest,
+}
+
+impl ConcurrencyPolicy {
+    pub fn all() -> &'static [ConcurrencyPolicy] {
+        &[ConcurrencyPolicy::Refuse, ConcurrencyPolicy::StopOldest]
+    }
+}
+
+impl std::fmt::Display for ConcurrencyPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConcurrencyPolicy::Refuse => write!(f, "{}", fl!("concurrency-refuse")),
+            ConcurrencyPolicy::StopOldest => write!(f, "{}", fl!("concurrency-stop-oldest")),
+        }
+    }
+}
+
+// Limiter on the master bus, keeps stacked tracks from clipping
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimiterSettings {
+    // Whether the master bus is limited at all
+    pub enabled: bool,
+    // Level in dBFS above which the output is compressed
+    pub threshold: f32,
+}
+
+impl Default for LimiterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: -6.0,
         }
     }
 }
@@ -166,6 +845,27 @@ pub struct AppConfig {
     pub files: FileSettings,
     // Window settings
     pub window: WindowSettings,
+    // Saved track mixes
+    #[serde(default)]
+    pub presets: Vec<Preset>,
+    // Keyboard shortcuts
+    #[serde(default)]
+    pub keymap: KeymapSettings,
+    // Wake-up alarms
+    #[serde(default)]
+    pub alarms: Vec<Alarm>,
+    // Presets started at times of day
+    #[serde(default)]
+    pub schedule: Schedule,
+    // Focus session phases
+    #[serde(default)]
+    pub focus: FocusSettings,
+    // Last tone of the generator track
+    #[serde(default)]
+    pub generator: GeneratorSettings,
+    // Stereo position of each track by name, tracks missing here play centred
+    #[serde(default)]
+    pub pans: BTreeMap<String, f32>,
 }
 
 impl Default for AppConfig {
@@ -176,6 +876,356 @@ impl Default for AppConfig {
             ui: UiSettings::default(),
             files: FileSettings::default(),
             window: WindowSettings::default(),
+            presets: vec![],
+            keymap: KeymapSettings::default(),
+            alarms: vec![],
+            schedule: Schedule::default(),
+            focus: FocusSettings::default(),
+            generator: GeneratorSettings::default(),
+            pans: BTreeMap::new(),
+        }
+    }
+}
+
+// Keyboard shortcuts, each action accepts several bindings such as "space" or "ctrl+q"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeymapSettings {
+    // Pause all tracks, or resume them if nothing is playing
+    pub toggle_pause: Vec<String>,
+    // Focus the next track card
+    pub select_next: Vec<String>,
+    // Focus the previous track card
+    pub select_previous: Vec<String>,
+    // Play or pause the focused track
+    pub toggle_focused: Vec<String>,
+    // Raise the focused track or the master volume
+    pub volume_up: Vec<String>,
+    // Lower the focused track or the master volume
+    pub volume_down: Vec<String>,
+    // Leave the settings view or clear the focus
+    pub back: Vec<String>,
+    // Quit the application
+    pub quit: Vec<String>,
+}
+
+impl Default for KeymapSettings {
+    fn default() -> Self {
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect();
+        Self {
+            toggle_pause: keys(&["space"]),
+            select_next: keys(&["right", "down"]),
+            select_previous: keys(&["left", "up"]),
+            toggle_focused: keys(&["enter"]),
+            volume_up: keys(&["+", "="]),
+            volume_down: keys(&["-"]),
+            back: keys(&["escape"]),
+            quit: keys(&["ctrl+q"]),
+        }
+    }
+}
+
+// A saved mix: which tracks play and at what volume
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    // Name used to apply the preset
+    pub name: String,
+    // Tracks that are playing in this mix
+    pub tracks: Vec<PresetTrack>,
+}
+
+// A single track entry of a preset, matched by track name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetTrack {
+    // Track name
+    pub name: String,
+    // Volume level in decibels
+    pub volume: f32,
+    // Stereo position, centred in presets saved before panning existed
+    #[serde(default)]
+    pub pan: f32,
+    // Playback speed, recorded speed in presets saved before it existed
+    #[serde(default = "default_playback_rate")]
+    pub playback_rate: f32,
+    // Filter and EQ settings
+    #[serde(default)]
+    pub tone: ToneSettings,
+    // Volume drift, off in presets saved before drift existed
+    #[serde(default)]
+    pub drift: DriftSettings,
+    // Tone of the generator track
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<GeneratorSettings>,
+    // Granular texture, the file loops as usual when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granular: Option<GranularSettings>,
+}
+
+fn default_playback_rate() -> f32 {
+    1.0
+}
+
+impl Preset {
+    // Capture the currently playing tracks as a preset
+    pub fn from_tracks(name: String, tracks: &[NoiseTrack]) -> Self {
+        Self {
+            name,
+            tracks: tracks
+                .iter()
+                .filter(|track| matches!(track.state, PlaybackState::Playing))
+                .map(|track| PresetTrack {
+                    name: track.name.clone(),
+                    volume: track.volume_level,
+                    pan: track.pan,
+                    playback_rate: track.playback_rate,
+                    tone: track.tone,
+                    drift: track.drift,
+                    generator: track.source.generator(),
+                    granular: track.source.granular(),
+                })
+                .collect(),
+        }
+    }
+
+    // Get the preset entry for a track, if the track is part of the mix
+    pub fn track(&self, name: &str) -> Option<&PresetTrack> {
+        self.tracks.iter().find(|track| track.name == name)
+    }
+}
+
+// Longest fade-in offered for an alarm
+pub const MAX_ALARM_FADE_MINUTES: u32 = 60;
+
+// Days of the week in the order they are shown
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+// Wake-up alarm: starts a preset from silence and slowly fades it in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alarm {
+    // Time of day the alarm rings
+    pub time: NaiveTime,
+    // Days the alarm rings on, every day when empty
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    // Preset started when the alarm rings
+    pub preset: String,
+    // Minutes the master level takes to go from silence to full volume
+    pub fade_minutes: u32,
+    // Disabled alarms are kept but never ring
+    pub enabled: bool,
+}
+
+impl Alarm {
+    // Whether the alarm rings during the minute of `at`
+    pub fn rings_at(&self, at: NaiveDateTime) -> bool {
+        self.enabled
+            && at.hour() == self.time.hour()
+            && at.minute() == self.time.minute()
+            && (self.days.is_empty() || self.days.contains(&at.weekday()))
+    }
+
+    // Short description of the days, e.g. "Mon Tue Wed"
+    pub fn days_label(&self) -> String {
+        if self.days.is_empty() {
+            return fl!("every-day");
+        }
+
+        WEEKDAYS
+            .iter()
+            .filter(|day| self.days.contains(day))
+            .map(|day| day.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+// Alarm being entered in the settings, before it is added
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlarmDraft {
+    // Time as typed, "HH:MM"
+    pub time: String,
+    pub preset: Option<String>,
+    pub days: Vec<Weekday>,
+    pub fade_minutes: u32,
+}
+
+impl AlarmDraft {
+    // The alarm to add, if the time is valid and a preset is chosen
+    pub fn alarm(&self) -> Option<Alarm> {
+        Some(Alarm {
+            time: NaiveTime::parse_from_str(self.time.trim(), "%H:%M").ok()?,
+            days: self.days.clone(),
+            preset: self.preset.clone()?,
+            fade_minutes: self.fade_minutes,
+            enabled: true,
+        })
+    }
+
+    // Select or unselect a day
+    pub fn toggle_day(&mut self, day: Weekday) {
+        if self.days.contains(&day) {
+            self.days.retain(|selected| *selected != day);
+        } else {
+            self.days.push(day);
+        }
+    }
+}
+
+impl Default for AlarmDraft {
+    fn default() -> Self {
+        Self {
+            time: "07:00".to_string(),
+            preset: None,
+            days: vec![],
+            fade_minutes: 15,
+        }
+    }
+}
+
+// Longest focus phase offered in the settings
+pub const MAX_FOCUS_MINUTES: u32 = 120;
+
+// Longest crossfade and sleep timer offered in the schedule editor
+pub const MAX_CROSSFADE_SECONDS: u64 = 600;
+pub const MAX_SCHEDULE_SLEEP_MINUTES: u64 = 240;
+
+// Preset started at a time of day by the schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    // Time of day the preset takes over
+    pub time: NaiveTime,
+    pub preset: String,
+    // Sleep timer started along with the preset
+    #[serde(default)]
+    pub sleep_minutes: Option<u64>,
+}
+
+// Daily timeline of presets, crossfading at each entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    pub enabled: bool,
+    // Length of the crossfade between two presets
+    pub crossfade_seconds: u64,
+    // Entries sorted by time
+    pub entries: Vec<ScheduleEntry>,
+}
+
+impl Schedule {
+    // Entry taking over during the minute of `at`
+    pub fn entry_at(&self, at: NaiveDateTime) -> Option<&ScheduleEntry> {
+        if !self.enabled {
+            return None;
+        }
+
+        self.entries
+            .iter()
+            .find(|entry| entry.time.hour() == at.hour() && entry.time.minute() == at.minute())
+    }
+
+    // Add an entry, replacing one at the same time
+    pub fn insert(&mut self, entry: ScheduleEntry) {
+        self.entries.retain(|existing| existing.time != entry.time);
+        self.entries.push(entry);
+        self.entries.sort_by_key(|entry| entry.time);
+    }
+
+    // Parts of the day as `(minutes, entry)`, from midnight. The first part
+    // still belongs to the last entry of the previous day.
+    pub fn segments(&self) -> Vec<(u32, &ScheduleEntry)> {
+        let (Some(first), Some(last)) = (self.entries.first(), self.entries.last()) else {
+            return vec![];
+        };
+        let minute_of_day = |time: NaiveTime| time.num_seconds_from_midnight() / 60;
+
+        let mut segments = vec![];
+        if minute_of_day(first.time) > 0 {
+            segments.push((minute_of_day(first.time), last));
+        }
+        for (index, entry) in self.entries.iter().enumerate() {
+            let end = self
+                .entries
+                .get(index + 1)
+                .map_or(24 * 60, |next| minute_of_day(next.time));
+            segments.push((end - minute_of_day(entry.time), entry));
+        }
+        segments
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            crossfade_seconds: 60,
+            entries: vec![],
+        }
+    }
+}
+
+// Schedule entry being entered in the settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleDraft {
+    // Time as typed, "HH:MM"
+    pub time: String,
+    pub preset: Option<String>,
+    // Sleep timer to start with the preset, none when zero
+    pub sleep_minutes: u64,
+}
+
+impl ScheduleDraft {
+    // The entry to add, if the time is valid and a preset is chosen
+    pub fn entry(&self) -> Option<ScheduleEntry> {
+        Some(ScheduleEntry {
+            time: NaiveTime::parse_from_str(self.time.trim(), "%H:%M").ok()?,
+            preset: self.preset.clone()?,
+            sleep_minutes: (self.sleep_minutes > 0).then_some(self.sleep_minutes),
+        })
+    }
+}
+
+impl Default for ScheduleDraft {
+    fn default() -> Self {
+        Self {
+            time: "08:00".to_string(),
+            preset: None,
+            sleep_minutes: 0,
+        }
+    }
+}
+
+// Lengths and presets of the focus session phases
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FocusSettings {
+    pub work_minutes: u32,
+    pub break_minutes: u32,
+    // Presets crossfaded to when a phase starts, the mix is kept when unset
+    pub work_preset: Option<String>,
+    pub break_preset: Option<String>,
+    // Play a chime when the phase changes
+    pub chime: bool,
+    // Length of the crossfade between the phase presets
+    pub crossfade_seconds: u64,
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            break_minutes: 5,
+            work_preset: None,
+            break_preset: None,
+            chime: true,
+            crossfade_seconds: 10,
         }
     }
 }
@@ -193,6 +1243,15 @@ pub struct UiSettings {
     pub grid_columns: Option<usize>,
     // Show track metadata
     pub show_metadata: bool,
+    // Hide to the system tray instead of exiting when the window is closed
+    #[serde(default)]
+    pub close_to_tray: bool,
+    // Names of the tracks marked as favorite
+    #[serde(default)]
+    pub favorites: Vec<String>,
+    // Categories whose section is collapsed in the player view
+    #[serde(default)]
+    pub collapsed_sections: Vec<String>,
 }
 
 impl Default for UiSettings {
@@ -203,6 +1262,9 @@ impl Default for UiSettings {
             enable_animations: true,
             grid_columns: None,
             show_metadata: false,
+            close_to_tray: false,
+            favorites: vec![],
+            collapsed_sections: vec![],
         }
     }
 }
@@ -321,12 +1383,46 @@ impl Default for WindowSettings {
 // Constants used throughout the application
 // Default volume in decibels
 pub const DEFAULT_VOLUME_DB: f32 = -30.0;
-#[allow(dead_code)]
 pub const MAX_VOLUME_DB: f32 = 0.0;
-#[allow(dead_code)]
 pub const MIN_VOLUME_DB: f32 = -60.0;
+// Range of the filter cutoffs, the extremes switch a filter off
+pub const MIN_CUTOFF_HZ: f32 = 20.0;
+pub const MAX_CUTOFF_HZ: f32 = 20_000.0;
+// Range of the EQ band gains in decibels
+pub const MAX_EQ_GAIN_DB: f32 = 12.0;
+// Name and section of the built-in generator track
+pub const GENERATOR_TRACK: &str = "Binaural beats";
+pub const GENERATOR_CATEGORY: &str = "Generators";
+// Range of the generator controls in Hz
+pub const MIN_CARRIER_HZ: f32 = 40.0;
+pub const MAX_CARRIER_HZ: f32 = 1_000.0;
+pub const MIN_BEAT_HZ: f32 = 0.5;
+pub const MAX_BEAT_HZ: f32 = 40.0;
+// Range of the granular texture controls
+pub const MIN_GRAIN_SIZE_MS: f32 = 10.0;
+pub const MAX_GRAIN_SIZE_MS: f32 = 500.0;
+pub const MIN_GRAIN_DENSITY: f32 = 1.0;
+pub const MAX_GRAIN_DENSITY: f32 = 100.0;
+pub const MAX_PITCH_JITTER: f32 = 12.0;
+// Range of the playback rate, an octave down to an octave up
+pub const MIN_PLAYBACK_RATE: f32 = 0.5;
+pub const MAX_PLAYBACK_RATE: f32 = 2.0;
+// Range of the volume drift controls
+pub const MAX_DRIFT_DEPTH_DB: f32 = 12.0;
+pub const MIN_DRIFT_RATE: f32 = 0.5;
+pub const MAX_DRIFT_RATE: f32 = 20.0;
+// Lowest threshold offered for the master limiter
+pub const MIN_LIMITER_THRESHOLD_DB: f32 = -24.0;
 // Supported audio file extensions
 pub const SUPPORTED_EXTENSIONS: &[&str] = &["mp3", "ogg", "flac", "wav"];
+// Extension of the metadata file that can sit next to a track
+pub const SIDECAR_EXTENSION: &str = "toml";
+// Section name of tracks without a category
+pub const UNCATEGORIZED: &str = "Other";
+// File that turns a folder of clips into an event track
+pub const EVENT_FILE: &str = "event.toml";
+// File that turns a folder of recordings into a variation track
+pub const VARIATIONS_FILE: &str = "variations.toml";
 // Default sound directory name
 pub const SOUND_DIRECTORY: &str = "cosmic-noise/sounds";
 
@@ -342,6 +1438,83 @@ mod tests {
         assert_eq!(track.volume_level, DEFAULT_VOLUME_DB);
     }
 
+    #[test]
+    fn test_track_filter() {
+        let mut track = NoiseTrack::new("rain".to_string(), PathBuf::from("/test/rain.ogg"));
+        track.category = Some("Weather".to_string());
+        track.tags = vec!["calm".to_string()];
+
+        let filter = |query: &str| TrackFilter {
+            query: query.to_string(),
+            ..TrackFilter::default()
+        };
+        assert!(filter("").matches(&track));
+        assert!(filter("RAI").matches(&track));
+        assert!(filter("weather").matches(&track));
+        assert!(filter("calm").matches(&track));
+        assert!(!filter("city").matches(&track));
+
+        let playing_only = TrackFilter {
+            playing_only: true,
+            ..TrackFilter::default()
+        };
+        assert!(!playing_only.matches(&track));
+        track.state = PlaybackState::Playing;
+        assert!(playing_only.matches(&track));
+
+        let favorites_only = TrackFilter {
+            favorites_only: true,
+            ..TrackFilter::default()
+        };
+        assert!(!favorites_only.matches(&track));
+        track.favorite = true;
+        assert!(favorites_only.matches(&track));
+    }
+
+    #[test]
+    fn test_group_sections() {
+        let mut tracks: Vec<NoiseTrack> = ["rain", "city", "wind", "white"]
+            .iter()
+            .map(|name| NoiseTrack::new(name.to_string(), PathBuf::from(name)))
+            .collect();
+        tracks[0].category = Some("Weather".to_string());
+        tracks[1].category = Some("Urban".to_string());
+        tracks[2].category = Some("Weather".to_string());
+        tracks[2].state = PlaybackState::Playing;
+
+        let sections = TrackSection::group(&tracks, 0..tracks.len());
+        let names: Vec<&str> = sections.iter().map(TrackSection::name).collect();
+        assert_eq!(names, vec!["Urban", "Weather", UNCATEGORIZED]);
+        assert_eq!(sections[1].tracks, vec![0, 2]);
+        assert_eq!(sections[1].playing_count(&tracks), 1);
+        assert!(TrackSection::is_grouped(&sections));
+
+        let flat = TrackSection::group(&tracks, [3]);
+        assert!(!TrackSection::is_grouped(&flat));
+    }
+
+    #[test]
+    fn test_parse_sidecar() {
+        let sidecar: TrackSidecar =
+            toml::from_str("category = \"Nature\"\ntags = [\"calm\"]").unwrap();
+        assert_eq!(sidecar.category.as_deref(), Some("Nature"));
+        assert_eq!(sidecar.tags, vec!["calm".to_string()]);
+
+        let empty: TrackSidecar = toml::from_str("").unwrap();
+        assert_eq!(empty, TrackSidecar::default());
+    }
+
+    #[test]
+    fn test_parse_event_sidecar() {
+        let sidecar: EventSidecar =
+            toml::from_str("category = \"Weather\"\nrate = 0.5\npan_spread = 1.0").unwrap();
+        assert_eq!(sidecar.category.as_deref(), Some("Weather"));
+        assert_eq!(sidecar.settings.rate, 0.5);
+        assert_eq!(sidecar.settings.pan_spread, 1.0);
+        // Unset fields keep their defaults
+        assert_eq!(sidecar.settings.jitter, EventSettings::default().jitter);
+    }
+
     #[test]
     fn test_app_config_defaults() {
         let config = AppConfig::default();
@@ -350,6 +1523,155 @@ mod tests {
         assert_eq!(config.window.width, 800.0);
     }
 
+    #[test]
+    fn test_preset_from_tracks() {
+        let mut rain = NoiseTrack::new("rain".to_string(), PathBuf::from("/test/rain.ogg"));
+        rain.state = PlaybackState::Playing;
+        rain.volume_level = -12.0;
+        rain.pan = -0.5;
+        let waves = NoiseTrack::new("waves".to_string(), PathBuf::from("/test/waves.ogg"));
+
+        let preset = Preset::from_tracks("Evening".to_string(), &[rain, waves]);
+        assert_eq!(preset.tracks.len(), 1);
+        assert_eq!(preset.track("rain").map(|t| t.volume), Some(-12.0));
+        assert_eq!(preset.track("rain").map(|t| t.pan), Some(-0.5));
+        assert!(preset.track("waves").is_none());
+    }
+
+    #[test]
+    fn test_preset_without_pan() {
+        // Presets saved by older versions have no pan and play centred
+        let track: PresetTrack = toml::from_str("name = \"rain\"\nvolume = -12.0").unwrap();
+        assert_eq!(track.pan, 0.0);
+        assert_eq!(track.playback_rate, 1.0);
+        assert!(track.tone.is_flat());
+        assert!(track.drift.is_off());
+        assert_eq!(track.granular, None);
+    }
+
+    #[test]
+    fn test_brainwave_bands() {
+        for band in BrainwaveBand::all() {
+            assert_eq!(BrainwaveBand::of(band.beat()), *band);
+        }
+        assert_eq!(BrainwaveBand::of(40.0), BrainwaveBand::Beta);
+
+        let track = NoiseTrack::generator(GeneratorSettings::default());
+        assert_eq!(track.category.as_deref(), Some(GENERATOR_CATEGORY));
+        assert_eq!(track.source.generator().unwrap().beat, 10.0);
+        assert_eq!(track.source.file(), None);
+    }
+
+    #[test]
+    fn test_granular_source() {
+        let source = SoundSource::File(PathBuf::from("rain.ogg"));
+        let settings = GranularSettings::default();
+        let granular = source.clone().with_granular(Some(settings));
+        assert_eq!(granular.file(), Some(Path::new("rain.ogg")));
+        assert_eq!(granular.granular(), Some(settings));
+        assert_eq!(granular.with_granular(None), source);
+
+        // Folders of clips have no granular mode
+        let events = SoundSource::Events(PathBuf::from("thunder"), EventSettings::default());
+        assert_eq!(events.clone().with_granular(Some(settings)), events);
+    }
+
+    #[test]
+    fn test_drift_range() {
+        let drift = DriftSettings {
+            depth: 6.0,
+            ..DriftSettings::default()
+        };
+        assert_eq!(drift.range(-30.0), (-36.0, -24.0));
+        // The range stays within the volume slider
+        assert_eq!(drift.range(-3.0), (-9.0, MAX_VOLUME_DB));
+        assert_eq!(drift.period(), Duration::from_secs(15));
+    }
+
+    #[test]
+    fn test_alarm_rings() {
+        let mut alarm = AlarmDraft {
+            preset: Some("Morning birds".to_string()),
+            days: vec![Weekday::Mon, Weekday::Fri],
+            ..AlarmDraft::default()
+        }
+        .alarm()
+        .unwrap();
+        assert_eq!(alarm.days_label(), "Mon Fri");
+
+        // 2024-01-01 was a Monday, 2024-01-02 a Tuesday
+        let monday = NaiveDateTime::parse_from_str("2024-01-01 07:00:30", "%Y-%m-%d %H:%M:%S");
+        let tuesday = NaiveDateTime::parse_from_str("2024-01-02 07:00:00", "%Y-%m-%d %H:%M:%S");
+        let late = NaiveDateTime::parse_from_str("2024-01-01 07:01:00", "%Y-%m-%d %H:%M:%S");
+        assert!(alarm.rings_at(monday.unwrap()));
+        assert!(!alarm.rings_at(tuesday.unwrap()));
+        assert!(!alarm.rings_at(late.unwrap()));
+
+        alarm.enabled = false;
+        assert!(!alarm.rings_at(monday.unwrap()));
+    }
+
+    #[test]
+    fn test_alarm_draft() {
+        let draft = AlarmDraft {
+            time: "25:00".to_string(),
+            preset: Some("Morning birds".to_string()),
+            ..AlarmDraft::default()
+        };
+        assert!(draft.alarm().is_none());
+        assert!(AlarmDraft::default().alarm().is_none());
+
+        let mut draft = AlarmDraft::default();
+        draft.toggle_day(Weekday::Sat);
+        draft.toggle_day(Weekday::Sun);
+        draft.toggle_day(Weekday::Sat);
+        assert_eq!(draft.days, [Weekday::Sun]);
+    }
+
+    #[test]
+    fn test_schedule() {
+        let entry = |time: &str, preset: &str| {
+            ScheduleDraft {
+                time: time.to_string(),
+                preset: Some(preset.to_string()),
+                sleep_minutes: 0,
+            }
+            .entry()
+            .unwrap()
+        };
+        let mut schedule = Schedule::default();
+        schedule.insert(entry("18:00", "Evening rain"));
+        schedule.insert(entry("08:00", "Focus café"));
+        schedule.insert(entry("18:00", "Storm"));
+
+        let names: Vec<&str> = schedule.entries.iter().map(|e| e.preset.as_str()).collect();
+        assert_eq!(names, ["Focus café", "Storm"]);
+
+        let at = |time: &str| {
+            NaiveDateTime::parse_from_str(&format!("2024-01-01 {time}"), "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+        };
+        assert_eq!(
+            schedule.entry_at(at("18:00:42")).map(|e| e.preset.as_str()),
+            Some("Storm")
+        );
+        assert!(schedule.entry_at(at("18:01:00")).is_none());
+
+        // Midnight to 08:00 still plays the evening preset
+        let segments: Vec<(u32, &str)> = schedule
+            .segments()
+            .into_iter()
+            .map(|(minutes, entry)| (minutes, entry.preset.as_str()))
+            .collect();
+        assert_eq!(
+            segments,
+            [(480, "Storm"), (600, "Focus café"), (360, "Storm")]
+        );
+
+        schedule.enabled = false;
+        assert!(schedule.entry_at(at("18:00:00")).is_none());
+    }
+
     #[test]
     fn test_theme_display() {
         assert_eq!(AppTheme::GruvboxLight.display_name(), "Gruvbox Light");
@@ -0,0 +1,396 @@
+//! MPRIS2 interface on the session bus.
+//!
+//! Exposes `org.mpris.MediaPlayer2` and `org.mpris.MediaPlayer2.Player` so
+//! desktop media widgets, media keys and `playerctl` can control playback.
+//! Incoming calls are turned into [`Request`]s for the application inbox, and
+//! the application pushes its state back with [`Server::update`].
+
+use std::collections::HashMap;
+
+use iced::futures::channel::mpsc::UnboundedSender;
+use kira::sound::PlaybackState;
+use zbus::blocking::connection;
+use zbus::zvariant::{ObjectPath, OwnedValue, Str, Value};
+use zbus::{block_on, interface};
+
+use crate::audio::{db_to_percentage, percentage_to_db};
+use crate::ipc::{Incoming, Request};
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.cosmic_noise";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+// The whole mix is presented as a single "track"
+const MIX_TRACK_ID: &str = "/io/github/bqwrongway/CosmicNoise/Mix";
+const NO_TRACK_ID: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+// Snapshot of the player state published over D-Bus
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerState {
+    // Global playback state of the audio system
+    pub playback: PlaybackState,
+    // Master volume in decibels
+    pub master_volume: f32,
+    // Names of the tracks that are currently audible
+    pub active_tracks: Vec<String>,
+}
+
+impl Default for PlayerState {
+    fn default() -> Self {
+        Self {
+            playback: PlaybackState::Stopped,
+            master_volume: crate::models::DEFAULT_VOLUME_DB,
+            active_tracks: vec![],
+        }
+    }
+}
+
+impl PlayerState {
+    // MPRIS playback status string
+    fn playback_status(&self) -> &'static str {
+        match self.playback {
+            PlaybackState::Playing | PlaybackState::Resuming => "Playing",
+            PlaybackState::Paused | PlaybackState::Pausing | PlaybackState::WaitingToResume => {
+                "Paused"
+            }
+            PlaybackState::Stopping | PlaybackState::Stopped => "Stopped",
+        }
+    }
+
+    // Master volume on the linear 0.0 - 1.0 scale MPRIS expects
+    fn volume(&self) -> f64 {
+        f64::from(db_to_percentage(self.master_volume)) / 100.0
+    }
+}
+
+// `org.mpris.MediaPlayer2`
+struct Root {
+    inbox: UnboundedSender<Incoming>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {
+        let _ = self.inbox.unbounded_send(Request::Show.into());
+    }
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "Cosmic Noise"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        vec![]
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+// `org.mpris.MediaPlayer2.Player`
+struct Player {
+    inbox: UnboundedSender<Incoming>,
+    state: PlayerState,
+}
+
+impl Player {
+    fn send(&self, request: Request) {
+        let _ = self.inbox.unbounded_send(request.into());
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn play(&self) {
+        self.send(Request::ResumeAll);
+    }
+
+    fn pause(&self) {
+        self.send(Request::PauseAll);
+    }
+
+    fn play_pause(&self) {
+        if self.state.playback_status() == "Playing" {
+            self.send(Request::PauseAll);
+        } else {
+            self.send(Request::ResumeAll);
+        }
+    }
+
+    fn stop(&self) {
+        self.send(Request::StopAll);
+    }
+
+    // Ambient mixes have no notion of next/previous or seeking
+    fn next(&self) {}
+
+    fn previous(&self) {}
+
+    fn seek(&self, _offset: i64) {}
+
+    fn set_position(&self, _track_id: ObjectPath<'_>, _position: i64) {}
+
+    fn open_uri(&self, _uri: &str) {}
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        self.state.playback_status()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::fdo::Result<HashMap<String, OwnedValue>> {
+        let track_id = if self.state.active_tracks.is_empty() {
+            NO_TRACK_ID
+        } else {
+            MIX_TRACK_ID
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "mpris:trackid".to_string(),
+            OwnedValue::from(ObjectPath::from_str_unchecked(track_id)),
+        );
+        metadata.insert(
+            "xesam:title".to_string(),
+            OwnedValue::from(Str::from(self.state.active_tracks.join(", "))),
+        );
+        metadata.insert(
+            "xesam:artist".to_string(),
+            OwnedValue::try_from(Value::from(self.state.active_tracks.clone()))
+                .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?,
+        );
+        metadata.insert(
+            "xesam:album".to_string(),
+            OwnedValue::from(Str::from("Cosmic Noise")),
+        );
+        Ok(metadata)
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.state.volume()
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        let percent = (volume.clamp(0.0, 1.0) * 100.0) as f32;
+        self.state.master_volume = percentage_to_db(percent);
+        self.send(Request::SetMaster(percent));
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        0
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+// Connection owning the MPRIS objects
+pub struct Server {
+    connection: zbus::blocking::Connection,
+}
+
+impl Server {
+    // Register on the session bus
+    pub fn start(inbox: UnboundedSender<Incoming>) -> zbus::Result<Self> {
+        Self::start_on(connection::Builder::session()?, inbox)
+    }
+
+    // Register on the bus the given builder connects to
+    pub fn start_on(
+        builder: connection::Builder<'_>,
+        inbox: UnboundedSender<Incoming>,
+    ) -> zbus::Result<Self> {
+        let connection = builder
+            .name(BUS_NAME)?
+            .serve_at(
+                OBJECT_PATH,
+                Root {
+                    inbox: inbox.clone(),
+                },
+            )?
+            .serve_at(
+                OBJECT_PATH,
+                Player {
+                    inbox,
+                    state: PlayerState::default(),
+                },
+            )?
+            .build()?;
+
+        log::info!("Registered MPRIS interface as {BUS_NAME}");
+        Ok(Self { connection })
+    }
+
+    // Publish a new player state, emitting change signals for what differs
+    pub fn update(&self, state: &PlayerState) -> zbus::Result<()> {
+        let player = self
+            .connection
+            .object_server()
+            .interface::<_, Player>(OBJECT_PATH)?;
+        let mut iface = player.get_mut();
+        if iface.state == *state {
+            return Ok(());
+        }
+
+        let previous = std::mem::replace(&mut iface.state, state.clone());
+        let emitter = player.signal_emitter();
+        block_on(async {
+            if previous.playback_status() != state.playback_status() {
+                iface.playback_status_changed(emitter).await?;
+            }
+            if previous.master_volume != state.master_volume {
+                iface.volume_changed(emitter).await?;
+            }
+            if previous.active_tracks != state.active_tracks {
+                iface.metadata_changed(emitter).await?;
+            }
+            Ok::<_, zbus::Error>(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::StreamExt;
+    use iced::futures::channel::mpsc;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+
+    // Private bus daemon, killed when the test ends even if an assertion failed
+    struct Daemon(Child);
+
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    #[test]
+    fn test_playback_status_mapping() {
+        let mut state = PlayerState::default();
+        assert_eq!(state.playback_status(), "Stopped");
+        state.playback = PlaybackState::Playing;
+        assert_eq!(state.playback_status(), "Playing");
+        state.playback = PlaybackState::Paused;
+        assert_eq!(state.playback_status(), "Paused");
+
+        state.master_volume = 0.0;
+        assert_eq!(state.volume(), 1.0);
+    }
+
+    // Runs against a private `dbus-daemon --session`, run it with `cargo test -- --ignored`
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn test_player_on_private_bus() {
+        let mut daemon = Daemon(
+            Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("dbus-daemon is not installed"),
+        );
+        let mut address = String::new();
+        BufReader::new(daemon.0.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+
+        let (inbox, mut requests) = mpsc::unbounded();
+        let server =
+            Server::start_on(connection::Builder::address(address.trim()).unwrap(), inbox).unwrap();
+        server
+            .update(&PlayerState {
+                playback: PlaybackState::Playing,
+                master_volume: -30.0,
+                active_tracks: vec!["rain".to_string()],
+            })
+            .unwrap();
+
+        let client = connection::Builder::address(address.trim())
+            .unwrap()
+            .build()
+            .unwrap();
+        let proxy = zbus::blocking::Proxy::new(
+            &client,
+            BUS_NAME,
+            OBJECT_PATH,
+            "org.mpris.MediaPlayer2.Player",
+        )
+        .unwrap();
+
+        let status: String = proxy.get_property("PlaybackStatus").unwrap();
+        assert_eq!(status, "Playing");
+        let volume: f64 = proxy.get_property("Volume").unwrap();
+        assert_eq!(volume, 0.5);
+
+        let _: () = proxy.call("PlayPause", &()).unwrap();
+        let _: () = proxy.call("Stop", &()).unwrap();
+        let mut next = || block_on(requests.next()).map(|incoming| incoming.request);
+        assert_eq!(next(), Some(Request::PauseAll));
+        assert_eq!(next(), Some(Request::StopAll));
+    }
+}
@@ -0,0 +1,454 @@
+//! Raw PCM output of the live mix.
+//!
+//! With `--pcm-out` the mixer is driven by `PcmBackend` instead of kira's
+//! cpal backend. On its own it renders on a wall clock thread and writes
+//! interleaved stereo samples to stdout or a file/FIFO, so another program
+//! can consume the mix (`cosmic_noise --headless --pcm-out - | ffmpeg ...`).
+//! With `--pcm-tee` the sound card keeps driving the renderer and every
+//! rendered buffer is copied to the PCM sink as well.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::sync_channel;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use kira::backend::Backend;
+use kira::backend::Renderer;
+use kira::backend::cpal::{CpalBackend, CpalBackendSettings};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+
+pub const DEFAULT_PCM_SAMPLE_RATE: u32 = 48_000;
+
+// Frames rendered per block when the PCM sink sets the pace
+const BLOCK_FRAMES: usize = 512;
+// Seconds of audio the sound card may get ahead of the sink before buffers are dropped
+const TEE_SECONDS: usize = 2;
+// How long the tee writer sleeps when it has caught up with the sound card
+const TEE_POLL: Duration = Duration::from_millis(5);
+
+// Sample encoding written to the sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PcmFormat {
+    // Signed 16 bit little endian
+    #[default]
+    S16Le,
+    // 32 bit float little endian
+    F32Le,
+}
+
+impl PcmFormat {
+    // Append one sample to `bytes`
+    pub fn encode(&self, sample: f32, bytes: &mut Vec<u8>) {
+        match self {
+            PcmFormat::S16Le => {
+                let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+            PcmFormat::F32Le => bytes.extend_from_slice(&sample.to_le_bytes()),
+        }
+    }
+}
+
+impl FromStr for PcmFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "s16le" => Ok(PcmFormat::S16Le),
+            "f32le" => Ok(PcmFormat::F32Le),
+            _ => Err(format!(
+                "unknown PCM format '{value}', expected s16le or f32le"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PcmFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcmFormat::S16Le => write!(f, "s16le"),
+            PcmFormat::F32Le => write!(f, "f32le"),
+        }
+    }
+}
+
+// Where and how the mix is written, from the command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcmOutput {
+    // File or named pipe, `-` for stdout
+    pub target: PathBuf,
+    // Ignored with `tee`, the sound card's rate is used then
+    pub sample_rate: u32,
+    pub format: PcmFormat,
+    // Keep playing on the sound card as well
+    pub tee: bool,
+}
+
+impl PcmOutput {
+    pub fn new(target: impl Into<PathBuf>) -> Self {
+        Self {
+            target: target.into(),
+            sample_rate: DEFAULT_PCM_SAMPLE_RATE,
+            format: PcmFormat::default(),
+            tee: false,
+        }
+    }
+
+    fn open(&self) -> io::Result<Box<dyn Write + Send>> {
+        if self.target.as_os_str() == "-" {
+            return Ok(Box::new(BufWriter::new(io::stdout())));
+        }
+
+        // Opening a FIFO blocks until a reader shows up, so this runs on the output thread
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.target)?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+}
+
+#[derive(Debug)]
+pub enum OutputError {
+    Device(kira::backend::cpal::Error),
+    Pcm(String),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputError::Device(e) => write!(f, "{e}"),
+            OutputError::Pcm(msg) => write!(f, "PCM output: {msg}"),
+        }
+    }
+}
+
+// Backend of the audio system: the sound card, or a PCM sink
+pub enum OutputBackend {
+    Device(CpalBackend),
+    Pcm(PcmBackend),
+}
+
+impl Backend for OutputBackend {
+    // `None` plays on the default sound card only
+    type Settings = Option<PcmOutput>;
+    type Error = OutputError;
+
+    fn setup(
+        settings: Option<PcmOutput>,
+        internal_buffer_size: usize,
+    ) -> Result<(Self, u32), OutputError> {
+        match settings {
+            None => CpalBackend::setup(CpalBackendSettings::default(), internal_buffer_size)
+                .map(|(backend, sample_rate)| (OutputBackend::Device(backend), sample_rate))
+                .map_err(OutputError::Device),
+            Some(output) => PcmBackend::setup(output)
+                .map(|(backend, sample_rate)| (OutputBackend::Pcm(backend), sample_rate)),
+        }
+    }
+
+    fn start(&mut self, renderer: Renderer) -> Result<(), OutputError> {
+        match self {
+            OutputBackend::Device(backend) => backend.start(renderer).map_err(OutputError::Device),
+            OutputBackend::Pcm(backend) => backend.start(renderer),
+        }
+    }
+}
+
+pub struct PcmBackend {
+    output: PcmOutput,
+    sample_rate: u32,
+}
+
+impl PcmBackend {
+    fn setup(output: PcmOutput) -> Result<(Self, u32), OutputError> {
+        let sample_rate = if output.tee {
+            default_output_config()?.sample_rate().0
+        } else {
+            output.sample_rate
+        };
+        log::info!(
+            "Writing {sample_rate} Hz {} stereo PCM to {}",
+            output.format,
+            output.target.display()
+        );
+
+        Ok((
+            Self {
+                output,
+                sample_rate,
+            },
+            sample_rate,
+        ))
+    }
+
+    fn start(&mut self, renderer: Renderer) -> Result<(), OutputError> {
+        if self.output.tee {
+            start_tee(renderer, self.output.clone())
+        } else {
+            let output = self.output.clone();
+            let sample_rate = self.sample_rate;
+            thread::Builder::new()
+                .name("pcm-output".to_string())
+                .spawn(move || render_clocked(renderer, &output, sample_rate))
+                .map(|_| ())
+                .map_err(|e| OutputError::Pcm(e.to_string()))
+        }
+    }
+}
+
+fn default_output_config() -> Result<cpal::SupportedStreamConfig, OutputError> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| OutputError::Pcm("no output device to play alongside".to_string()))?;
+    device
+        .default_output_config()
+        .map_err(|e| OutputError::Pcm(e.to_string()))
+}
+
+// Render on our own clock, as fast as the samples are due
+fn render_clocked(mut renderer: Renderer, output: &PcmOutput, sample_rate: u32) {
+    let mut sink = match output.open() {
+        Ok(sink) => sink,
+        Err(e) => {
+            log::error!("Cannot open PCM output {}: {e}", output.target.display());
+            return;
+        }
+    };
+
+    let mut block = vec![0.0; BLOCK_FRAMES * 2];
+    let mut bytes = Vec::with_capacity(block.len() * 4);
+    let started = Instant::now();
+    let mut rendered = 0u64;
+
+    loop {
+        renderer.on_start_processing();
+        renderer.process(&mut block, 2);
+
+        bytes.clear();
+        for sample in &block {
+            output.format.encode(*sample, &mut bytes);
+        }
+        if let Err(e) = sink.write_all(&bytes).and_then(|()| sink.flush()) {
+            log::error!("PCM output closed: {e}");
+            return;
+        }
+
+        rendered += BLOCK_FRAMES as u64;
+        let due = started + Duration::from_secs_f64(rendered as f64 / sample_rate as f64);
+        if let Some(wait) = due.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+}
+
+// Play on the sound card and copy every buffer to the PCM sink
+fn start_tee(renderer: Renderer, output: PcmOutput) -> Result<(), OutputError> {
+    let supported = default_output_config()?;
+    let sample_format = supported.sample_format();
+    let config = supported.config();
+    let channels = config.channels;
+
+    // Allocated up front so the audio callback never has to
+    let capacity = config.sample_rate.0 as usize * channels as usize * TEE_SECONDS;
+    let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+    let dropped = Arc::new(AtomicU64::new(0));
+    let tee = Tee {
+        producer,
+        dropped: dropped.clone(),
+    };
+
+    // cpal streams can't move between threads, so the stream lives on its own
+    let (ready, started) = sync_channel(1);
+    thread::Builder::new()
+        .name("pcm-device".to_string())
+        .spawn(move || {
+            let stream = cpal::default_host()
+                .default_output_device()
+                .ok_or_else(|| "no output device".to_string())
+                .and_then(|device| match sample_format {
+                    SampleFormat::F32 => build_tee_stream::<f32>(&device, &config, renderer, tee),
+                    SampleFormat::F64 => build_tee_stream::<f64>(&device, &config, renderer, tee),
+                    SampleFormat::I16 => build_tee_stream::<i16>(&device, &config, renderer, tee),
+                    SampleFormat::I32 => build_tee_stream::<i32>(&device, &config, renderer, tee),
+                    SampleFormat::U16 => build_tee_stream::<u16>(&device, &config, renderer, tee),
+                    other => Err(format!("unsupported sample format {other}")),
+                })
+                .and_then(|stream| stream.play().map(|()| stream).map_err(|e| e.to_string()));
+
+            match stream {
+                Ok(_stream) => {
+                    let _ = ready.send(Ok(()));
+                    loop {
+                        thread::park();
+                    }
+                }
+                Err(e) => {
+                    let _ = ready.send(Err(e));
+                }
+            }
+        })
+        .map_err(|e| OutputError::Pcm(e.to_string()))?;
+
+    thread::Builder::new()
+        .name("pcm-output".to_string())
+        .spawn(move || write_tee(consumer, &dropped, &output, channels))
+        .map_err(|e| OutputError::Pcm(e.to_string()))?;
+
+    started
+        .recv()
+        .map_err(|e| OutputError::Pcm(e.to_string()))?
+        .map_err(OutputError::Pcm)
+}
+
+// Render in f32 and convert to whatever the sound card takes
+fn build_tee_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut renderer: Renderer,
+    mut tee: Tee,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels;
+    // One second of mix, far more than any device buffer
+    let mut mix = vec![0.0; config.sample_rate.0 as usize * channels as usize];
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                if mix.len() < data.len() {
+                    mix.resize(data.len(), 0.0);
+                }
+                let mix = &mut mix[..data.len()];
+                renderer.on_start_processing();
+                renderer.process(mix, channels);
+                for (out, sample) in data.iter_mut().zip(mix.iter()) {
+                    *out = T::from_sample(*sample);
+                }
+                tee.push(mix);
+            },
+            |e| log::error!("Audio stream error: {e}"),
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
+
+// Audio callback end of the tee
+struct Tee {
+    producer: HeapProd<f32>,
+    // Buffers that didn't fit, reported by the writer
+    dropped: Arc<AtomicU64>,
+}
+
+impl Tee {
+    // Queue the buffer for the writer, never blocking or allocating. A buffer
+    // that doesn't fit is dropped whole so channels stay in order
+    fn push(&mut self, data: &[f32]) {
+        if self.producer.vacant_len() < data.len() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.producer.push_slice(data);
+    }
+}
+
+fn write_tee(mut consumer: HeapCons<f32>, dropped: &AtomicU64, output: &PcmOutput, channels: u16) {
+    let mut sink = match output.open() {
+        Ok(sink) => sink,
+        Err(e) => {
+            log::error!("Cannot open PCM output {}: {e}", output.target.display());
+            return;
+        }
+    };
+
+    // Whole frames only, the callback always queues whole buffers
+    let mut buffer = vec![0.0; BLOCK_FRAMES * channels.max(1) as usize];
+    let mut bytes = Vec::new();
+    loop {
+        let lost = dropped.swap(0, Ordering::Relaxed);
+        if lost > 0 {
+            log::warn!("PCM output is too slow, dropped {lost} buffers");
+        }
+
+        let read = consumer.pop_slice(&mut buffer);
+        if read == 0 {
+            thread::sleep(TEE_POLL);
+            continue;
+        }
+
+        bytes.clear();
+        for sample in to_stereo(&buffer[..read], channels) {
+            output.format.encode(sample, &mut bytes);
+        }
+        if let Err(e) = sink.write_all(&bytes).and_then(|()| sink.flush()) {
+            log::error!("PCM output closed: {e}");
+            return;
+        }
+    }
+}
+
+// Interleaved stereo from a device buffer with any channel count
+fn to_stereo(buffer: &[f32], channels: u16) -> impl Iterator<Item = f32> + '_ {
+    buffer
+        .chunks_exact(channels.max(1) as usize)
+        .flat_map(|frame| [frame[0], frame[frame.len().min(2) - 1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_samples() {
+        let mut bytes = Vec::new();
+        PcmFormat::S16Le.encode(1.0, &mut bytes);
+        PcmFormat::S16Le.encode(-2.0, &mut bytes);
+        assert_eq!(bytes, [0xff, 0x7f, 0x01, 0x80]);
+
+        bytes.clear();
+        PcmFormat::F32Le.encode(0.5, &mut bytes);
+        assert_eq!(bytes, 0.5f32.to_le_bytes());
+
+        assert_eq!("f32le".parse(), Ok(PcmFormat::F32Le));
+        assert!("mp3".parse::<PcmFormat>().is_err());
+    }
+
+    #[test]
+    fn test_to_stereo() {
+        let mono: Vec<f32> = to_stereo(&[0.1, 0.2], 1).collect();
+        assert_eq!(mono, [0.1, 0.1, 0.2, 0.2]);
+
+        let surround: Vec<f32> = to_stereo(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6], 3).collect();
+        assert_eq!(surround, [0.1, 0.2, 0.4, 0.5]);
+    }
+
+    #[test]
+    fn test_tee_drops_whole_buffers() {
+        let (producer, mut consumer) = HeapRb::<f32>::new(6).split();
+        let dropped = Arc::new(AtomicU64::new(0));
+        let mut tee = Tee {
+            producer,
+            dropped: dropped.clone(),
+        };
+
+        tee.push(&[0.1, 0.2, 0.3, 0.4]);
+        tee.push(&[0.5, 0.6, 0.7, 0.8]);
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+
+        let mut buffer = [0.0; 8];
+        assert_eq!(consumer.pop_slice(&mut buffer), 4);
+        assert_eq!(buffer[..4], [0.1, 0.2, 0.3, 0.4]);
+    }
+}
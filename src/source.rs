@@ -0,0 +1,208 @@
+//! Starting and driving the sound sources of tracks.
+//!
+//! A `SoundSource` describes what a track plays. Starting it builds the kira
+//! sounds of that kind and returns a `Voice`, the one handle the audio system
+//! pauses, stops and tweens whatever the source is. Adding a kind of source
+//! means a variant in `SoundSource`, its arm in `start`, and a `Voice` variant
+//! if it needs a handle of its own.
+
+use std::fmt;
+use std::ops::RangeFrom;
+use std::time::Duration;
+
+use kira::backend::Backend;
+use kira::sound::streaming::{StreamingSoundData, StreamingSoundHandle, StreamingSoundSettings};
+use kira::sound::{FromFileError, PlaySoundError, PlaybackState, SoundData};
+use kira::track::TrackHandle;
+use kira::{AudioManager, Panning, PlaybackRate, Tween};
+
+use crate::errors::{AppError, AudioError};
+use crate::events::EventLayer;
+use crate::generator::{GeneratorData, GeneratorHandle};
+use crate::granular::{GranularData, GranularHandle};
+use crate::models::SoundSource;
+use crate::variations::VariationLayer;
+
+// Where the sounds of a source play: the track's sub-track, or the main track
+// when the sub-track couldn't be created
+pub enum Output<'a, B: Backend> {
+    Track(&'a mut TrackHandle),
+    Main(&'a mut AudioManager<B>),
+}
+
+impl<B: Backend> Output<'_, B> {
+    pub fn play<D: SoundData>(&mut self, data: D) -> Result<D::Handle, PlaySoundError<D::Error>> {
+        match self {
+            Output::Track(track) => track.play(data),
+            Output::Main(manager) => manager.play(data),
+        }
+    }
+}
+
+// Level, position, speed and fade-in a source starts with
+#[derive(Debug, Clone)]
+pub struct Start {
+    // Effective volume in dB
+    pub volume: f32,
+    pub pan: f32,
+    pub rate: f32,
+    pub fade_in: Option<Tween>,
+    // Part of a looped file that repeats
+    pub loop_region: RangeFrom<f64>,
+}
+
+// Handle of a started source
+fn started<H>(result: Result<H, impl fmt::Display>) -> Result<H, AppError> {
+    result.map_err(|e| {
+        log::error!("Failed to play sound: {e}");
+        AppError::Audio(AudioError::HandleCreationFailed)
+    })
+}
+
+impl SoundSource {
+    // Build the sounds of the source and start playing them on `output`
+    pub fn start<B: Backend>(
+        &self,
+        mut output: Output<B>,
+        start: Start,
+    ) -> Result<Voice, AppError> {
+        let Start {
+            volume,
+            pan,
+            rate,
+            fade_in,
+            loop_region,
+        } = start;
+
+        let voice = match self {
+            SoundSource::File(path) => {
+                let settings = StreamingSoundSettings::new()
+                    .fade_in_tween(fade_in)
+                    .volume(volume)
+                    .panning(Panning(pan))
+                    .playback_rate(PlaybackRate(rate as f64))
+                    .loop_region(loop_region);
+                let data = StreamingSoundData::from_file(path)
+                    .map_err(|e| AppError::Audio(e.into()))?
+                    .with_settings(settings);
+                Voice::Stream(started(output.play(data))?)
+            }
+            SoundSource::Granular(path, settings) => {
+                let data = GranularData::load(path, *settings, volume, pan, rate, fade_in)?;
+                Voice::Granular(started(output.play(data))?)
+            }
+            // Clips are fired later on, from `Voice::advance`
+            SoundSource::Events(folder, settings) => {
+                Voice::Events(EventLayer::load(folder, *settings, volume, pan, rate)?)
+            }
+            SoundSource::Variations(folder, settings) => {
+                let layer =
+                    VariationLayer::start(folder, *settings, volume, pan, rate, fade_in, |data| {
+                        output.play(data)
+                    })?;
+                Voice::Variations(layer)
+            }
+            SoundSource::Generator(settings) => {
+                let data = GeneratorData::new(*settings, volume, pan, fade_in);
+                Voice::Generator(started(output.play(data))?)
+            }
+        };
+        Ok(voice)
+    }
+}
+
+// What plays a track: a looped stream, random one-shot clips, rotating variations,
+// a synthesized tone or noise, or a granular texture
+pub enum Voice {
+    Stream(StreamingSoundHandle<FromFileError>),
+    Events(EventLayer),
+    Variations(VariationLayer),
+    Generator(GeneratorHandle),
+    Granular(GranularHandle),
+}
+
+impl Voice {
+    pub fn state(&self) -> PlaybackState {
+        match self {
+            Voice::Stream(handle) => handle.state(),
+            Voice::Events(layer) => layer.state(),
+            Voice::Variations(layer) => layer.state(),
+            Voice::Generator(handle) => handle.state(),
+            Voice::Granular(handle) => handle.state(),
+        }
+    }
+
+    pub fn pause(&mut self, tween: Tween) {
+        match self {
+            Voice::Stream(handle) => handle.pause(tween),
+            Voice::Events(layer) => layer.pause(tween),
+            Voice::Variations(layer) => layer.pause(tween),
+            Voice::Generator(handle) => handle.pause(tween),
+            Voice::Granular(handle) => handle.pause(tween),
+        }
+    }
+
+    pub fn resume(&mut self, tween: Tween) {
+        match self {
+            Voice::Stream(handle) => handle.resume(tween),
+            Voice::Events(layer) => layer.resume(tween),
+            Voice::Variations(layer) => layer.resume(tween),
+            Voice::Generator(handle) => handle.resume(tween),
+            Voice::Granular(handle) => handle.resume(tween),
+        }
+    }
+
+    pub fn stop(&mut self, tween: Tween) {
+        match self {
+            Voice::Stream(handle) => handle.stop(tween),
+            Voice::Events(layer) => layer.stop(tween),
+            Voice::Variations(layer) => layer.stop(tween),
+            Voice::Generator(handle) => handle.stop(tween),
+            Voice::Granular(handle) => handle.stop(tween),
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32, tween: Tween) {
+        match self {
+            Voice::Stream(handle) => handle.set_volume(volume, tween),
+            Voice::Events(layer) => layer.set_volume(volume, tween),
+            Voice::Variations(layer) => layer.set_volume(volume, tween),
+            Voice::Generator(handle) => handle.set_volume(volume, tween),
+            Voice::Granular(handle) => handle.set_volume(volume, tween),
+        }
+    }
+
+    pub fn set_playback_rate(&mut self, rate: f32, tween: Tween) {
+        match self {
+            Voice::Stream(handle) => handle.set_playback_rate(PlaybackRate(rate as f64), tween),
+            Voice::Events(layer) => layer.set_playback_rate(rate, tween),
+            Voice::Variations(layer) => layer.set_playback_rate(rate, tween),
+            Voice::Generator(_) => {}
+            Voice::Granular(handle) => handle.set_playback_rate(rate),
+        }
+    }
+
+    pub fn set_panning(&mut self, pan: f32, tween: Tween) {
+        match self {
+            Voice::Stream(handle) => handle.set_panning(Panning(pan), tween),
+            Voice::Events(layer) => layer.set_panning(pan, tween),
+            Voice::Variations(layer) => layer.set_panning(pan, tween),
+            Voice::Generator(handle) => handle.set_panning(pan, tween),
+            Voice::Granular(handle) => handle.set_panning(pan, tween),
+        }
+    }
+
+    // Whether the voice schedules sounds itself and needs `advance` calls
+    pub fn needs_stepping(&self) -> bool {
+        matches!(self, Voice::Events(_) | Voice::Variations(_))
+    }
+
+    // Let `elapsed` pass, starting the clips or variations that came due on `output`
+    pub fn advance<B: Backend>(&mut self, elapsed: Duration, mut output: Output<B>) {
+        match self {
+            Voice::Events(layer) => layer.advance(elapsed, |clip| output.play(clip)),
+            Voice::Variations(layer) => layer.advance(elapsed, |data| output.play(data)),
+            Voice::Stream(_) | Voice::Generator(_) | Voice::Granular(_) => {}
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Plumbing shared by the sounds synthesized in the audio thread.
+//!
+//! A synthesized sound keeps a copy of its controls (settings, volume, pan and
+//! transport) and refreshes it from a shared state whenever the lock is free,
+//! so the audio thread never waits on the UI. Its gain ramps towards the target
+//! level to fade in, fade out and follow volume changes without clicks.
+
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use kira::Tween;
+use kira::sound::PlaybackState;
+
+use crate::models::MIN_VOLUME_DB;
+
+// Shortest gain ramp, avoids clicks on instant changes
+const MIN_RAMP_SECONDS: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transport {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+// Everything the handle can change while the sound plays
+#[derive(Debug, Clone, Copy)]
+struct Controls<S> {
+    settings: S,
+    // Track volume in dB
+    volume: f32,
+    pan: f32,
+    // Playback speed, for sounds made from recordings
+    rate: f32,
+    transport: Transport,
+    // Time the gain takes to reach a new volume or silence
+    ramp_seconds: f32,
+}
+
+struct Shared<S> {
+    controls: Mutex<Controls<S>>,
+    // Set by the sound once it has faded out after a stop
+    finished: AtomicBool,
+}
+
+fn amplitude(volume: f32) -> f32 {
+    if volume <= MIN_VOLUME_DB {
+        0.0
+    } else {
+        10f32.powf(volume / 20.0)
+    }
+}
+
+// Audio thread side of a synthesized sound
+pub struct Synth<S> {
+    shared: Arc<Shared<S>>,
+    // Copy of the shared controls, refreshed when the lock is free
+    controls: Controls<S>,
+    // Current linear gain, ramping towards the target
+    gain: f32,
+}
+
+impl<S: Copy> Synth<S> {
+    // Start playing at `volume`, fading in over `fade_in` if given
+    pub fn new(
+        settings: S,
+        volume: f32,
+        pan: f32,
+        rate: f32,
+        fade_in: Option<Tween>,
+    ) -> (Self, SynthHandle<S>) {
+        let controls = Controls {
+            settings,
+            volume,
+            pan,
+            rate,
+            transport: Transport::Playing,
+            ramp_seconds: fade_in.map_or(MIN_RAMP_SECONDS, |tween| tween.duration.as_secs_f32()),
+        };
+        let shared = Arc::new(Shared {
+            controls: Mutex::new(controls),
+            finished: AtomicBool::new(false),
+        });
+        let synth = Self {
+            shared: shared.clone(),
+            controls,
+            gain: 0.0,
+        };
+        (synth, SynthHandle { shared })
+    }
+
+    // Pick up changes from the handle, call from `Sound::on_start_processing`
+    pub fn refresh(&mut self) {
+        if let Ok(controls) = self.shared.controls.try_lock() {
+            self.controls = *controls;
+        }
+    }
+
+    pub fn settings(&self) -> S {
+        self.controls.settings
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.controls.rate
+    }
+
+    // Equal power left and right gains of the pan, unity in the centre
+    pub fn pan_gains(&self) -> (f32, f32) {
+        let angle = (self.controls.pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+        (angle.cos() * SQRT_2, angle.sin() * SQRT_2)
+    }
+
+    // Ramp that moves the gain to its target, to feed to `next_gain` once per frame
+    pub fn ramp(&self, dt: f64) -> (f32, f32) {
+        let target = match self.controls.transport {
+            Transport::Playing => amplitude(self.controls.volume),
+            Transport::Paused | Transport::Stopped => 0.0,
+        };
+        let step = dt as f32 / self.controls.ramp_seconds.max(MIN_RAMP_SECONDS);
+        (target, step)
+    }
+
+    // Gain of the next frame
+    pub fn next_gain(&mut self, (target, step): (f32, f32)) -> f32 {
+        self.gain = if self.gain < target {
+            (self.gain + step).min(target)
+        } else {
+            (self.gain - step).max(target)
+        };
+        self.gain
+    }
+
+    // Mark the sound finished once a stop has faded out, call at the end of `process`
+    pub fn settle(&self) {
+        if self.controls.transport == Transport::Stopped && self.gain == 0.0 {
+            self.shared.finished.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.shared.finished.load(Ordering::Relaxed)
+    }
+}
+
+// Control of a playing synthesized sound
+pub struct SynthHandle<S> {
+    shared: Arc<Shared<S>>,
+}
+
+impl<S: Copy> SynthHandle<S> {
+    fn controls(&self) -> MutexGuard<'_, Controls<S>> {
+        self.shared
+            .controls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn update(&mut self, tween: Tween, change: impl FnOnce(&mut Controls<S>)) {
+        let mut controls = self.controls();
+        controls.ramp_seconds = tween.duration.as_secs_f32();
+        change(&mut controls);
+    }
+
+    pub fn state(&self) -> PlaybackState {
+        if self.shared.finished.load(Ordering::Relaxed) {
+            return PlaybackState::Stopped;
+        }
+        match self.controls().transport {
+            Transport::Playing => PlaybackState::Playing,
+            Transport::Paused => PlaybackState::Paused,
+            Transport::Stopped => PlaybackState::Stopping,
+        }
+    }
+
+    // New parameters apply from the next processed block
+    pub fn set_settings(&mut self, settings: S) {
+        self.controls().settings = settings;
+    }
+
+    pub fn set_volume(&mut self, volume: f32, tween: Tween) {
+        self.update(tween, |controls| controls.volume = volume);
+    }
+
+    pub fn set_panning(&mut self, pan: f32, tween: Tween) {
+        self.update(tween, |controls| controls.pan = pan);
+    }
+
+    // Applies from the next processed block, sounds smooth the change themselves
+    pub fn set_playback_rate(&mut self, rate: f32) {
+        self.controls().rate = rate;
+    }
+
+    pub fn pause(&mut self, tween: Tween) {
+        self.update(tween, |controls| controls.transport = Transport::Paused);
+    }
+
+    pub fn resume(&mut self, tween: Tween) {
+        self.update(tween, |controls| controls.transport = Transport::Playing);
+    }
+
+    pub fn stop(&mut self, tween: Tween) {
+        self.update(tween, |controls| controls.transport = Transport::Stopped);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fades() {
+        let (mut synth, mut handle) = Synth::new((), 0.0, 0.0, 1.0, None);
+        let ramp = synth.ramp(0.01);
+        assert_eq!(synth.next_gain(ramp), 0.5);
+        assert_eq!(synth.next_gain(ramp), 1.0);
+        assert_eq!(synth.next_gain(ramp), 1.0);
+        let (left, right) = synth.pan_gains();
+        assert!((left - 1.0).abs() < 1e-6 && (right - 1.0).abs() < 1e-6);
+
+        handle.stop(Tween::default());
+        synth.refresh();
+        assert_eq!(handle.state(), PlaybackState::Stopping);
+        let ramp = synth.ramp(1.0);
+        assert_eq!(synth.next_gain(ramp), 0.0);
+        synth.settle();
+        assert!(synth.finished());
+        assert_eq!(handle.state(), PlaybackState::Stopped);
+    }
+}
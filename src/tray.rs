@@ -0,0 +1,176 @@
+//! System tray icon (StatusNotifierItem) with quick controls.
+//!
+//! Menu entries send [`Request`]s to the application inbox, the same way the
+//! control socket and MPRIS do. The application pushes state changes back
+//! with [`Tray::update`] so labels stay in sync.
+
+use iced::futures::channel::mpsc::UnboundedSender;
+use kira::sound::PlaybackState;
+use ksni::blocking::TrayMethods;
+use ksni::menu::{MenuItem, StandardItem, SubMenu};
+
+use crate::fl;
+use crate::ipc::{Incoming, Request};
+
+// Master volume levels offered in the tray menu, in percent
+const VOLUME_STEPS: &[f32] = &[10.0, 25.0, 50.0, 75.0, 100.0];
+// Sleep timer shortcuts offered in the tray menu, in minutes
+const SLEEP_STEPS: &[u64] = &[15, 30, 60, 90];
+
+// State the tray menu is built from
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrayState {
+    // Global playback state of the audio system
+    pub playback: PlaybackState,
+    // Names of the saved presets
+    pub presets: Vec<String>,
+    // Whether the main window is currently shown
+    pub window_visible: bool,
+}
+
+impl Default for TrayState {
+    fn default() -> Self {
+        Self {
+            playback: PlaybackState::Stopped,
+            presets: vec![],
+            window_visible: true,
+        }
+    }
+}
+
+struct Item {
+    inbox: UnboundedSender<Incoming>,
+    state: TrayState,
+}
+
+// Menu entry that sends a request when activated
+fn request_item(label: impl Into<String>, request: Request) -> MenuItem<Item> {
+    StandardItem {
+        label: label.into(),
+        activate: Box::new(move |item: &mut Item| {
+            let _ = item.inbox.unbounded_send(request.clone().into());
+        }),
+        ..Default::default()
+    }
+    .into()
+}
+
+impl ksni::Tray for Item {
+    fn id(&self) -> String {
+        "cosmic-noise".to_string()
+    }
+
+    fn title(&self) -> String {
+        "Cosmic Noise".to_string()
+    }
+
+    fn icon_name(&self) -> String {
+        "io.github.bqwrongway.CosmicNoise".to_string()
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        let request = if self.state.window_visible {
+            Request::Hide
+        } else {
+            Request::Show
+        };
+        let _ = self.inbox.unbounded_send(request.into());
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let play_pause = if matches!(self.state.playback, PlaybackState::Playing) {
+            request_item(fl!("tray-pause-all"), Request::PauseAll)
+        } else {
+            request_item(fl!("tray-resume-all"), Request::ResumeAll)
+        };
+
+        let volume = SubMenu {
+            label: fl!("tray-master-volume"),
+            submenu: VOLUME_STEPS
+                .iter()
+                .map(|percent| request_item(format!("{percent}%"), Request::SetMaster(*percent)))
+                .collect(),
+            ..Default::default()
+        };
+
+        let presets = SubMenu {
+            label: fl!("tray-presets"),
+            enabled: !self.state.presets.is_empty(),
+            submenu: self
+                .state
+                .presets
+                .iter()
+                .map(|name| request_item(name.clone(), Request::ApplyPreset(name.clone())))
+                .collect(),
+            ..Default::default()
+        };
+
+        let sleep = SubMenu {
+            label: fl!("tray-sleep-timer"),
+            submenu: SLEEP_STEPS
+                .iter()
+                .map(|minutes| {
+                    request_item(
+                        fl!("tray-minutes", minutes = *minutes),
+                        Request::SleepTimer(Some(*minutes)),
+                    )
+                })
+                .chain([request_item(fl!("off"), Request::SleepTimer(None))])
+                .collect(),
+            ..Default::default()
+        };
+
+        let window = if self.state.window_visible {
+            request_item(fl!("tray-hide"), Request::Hide)
+        } else {
+            request_item(fl!("tray-show"), Request::Show)
+        };
+
+        vec![
+            play_pause,
+            request_item(fl!("stop-icon"), Request::StopAll),
+            MenuItem::Separator,
+            volume.into(),
+            presets.into(),
+            sleep.into(),
+            MenuItem::Separator,
+            window,
+            request_item(fl!("tray-quit"), Request::Quit),
+        ]
+    }
+}
+
+// Running tray icon
+pub struct Tray {
+    handle: ksni::blocking::Handle<Item>,
+    // Last state sent to the tray service
+    state: TrayState,
+}
+
+impl Tray {
+    // Register the tray icon with the desktop's StatusNotifierWatcher
+    pub fn spawn(inbox: UnboundedSender<Incoming>, state: TrayState) -> Result<Self, ksni::Error> {
+        // Flatpak sandboxes don't allow owning the per-process well-known name
+        let sandboxed = std::path::Path::new("/.flatpak-info").exists();
+
+        let handle = Item {
+            inbox,
+            state: state.clone(),
+        }
+        .disable_dbus_name(sandboxed)
+        .spawn()?;
+
+        log::info!("System tray icon registered");
+        Ok(Self { handle, state })
+    }
+
+    // Refresh the menu if the state changed
+    pub fn update(&mut self, state: &TrayState) {
+        if self.state == *state {
+            return;
+        }
+
+        self.state = state.clone();
+        self.handle.update(|item| item.state = state.clone());
+    }
+}
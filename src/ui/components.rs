@@ -1,7 +1,20 @@
 
-use crate::audio::{db_to_percentage, percentage_to_db};
+use crate::audio::{
+    cutoff_to_position, db_to_percentage, percentage_to_db, position_to_cutoff, rate_to_semitones,
+    semitones_to_rate,
+};
 use crate::errors::{AppError, AudioError, FileSystemError};
-use crate::models::NoiseTrack;
+use crate::export::{ExportFormat, ExportOptions, MAX_EXPORT_MINUTES};
+use crate::focus::FocusHistory;
+use crate::models::{
+    Alarm, AlarmDraft, BrainwaveBand, ConcurrencyPolicy, DriftSettings, FocusSettings,
+    GeneratorMode, GeneratorSettings, GeneratorWaveform, GrainWindow, GranularSettings,
+    LimiterSettings, MAX_ALARM_FADE_MINUTES, MAX_BEAT_HZ, MAX_CARRIER_HZ, MAX_CROSSFADE_SECONDS,
+    MAX_DRIFT_DEPTH_DB, MAX_DRIFT_RATE, MAX_EQ_GAIN_DB, MAX_FOCUS_MINUTES, MAX_GRAIN_DENSITY,
+    MAX_GRAIN_SIZE_MS, MAX_PITCH_JITTER, MAX_SCHEDULE_SLEEP_MINUTES, MIN_BEAT_HZ, MIN_CARRIER_HZ,
+    MIN_DRIFT_RATE, MIN_GRAIN_DENSITY, MIN_GRAIN_SIZE_MS, MIN_LIMITER_THRESHOLD_DB, NoiseColor,
+    NoiseTrack, Preset, Schedule, ScheduleDraft, ToneSettings, TrackFilter, TrackSection, WEEKDAYS,
+};
 use crate::ui::styles;
 use crate::utils::dragwin;
 use crate::utils::sine_wave_loading::SineWaveLoading;
@@ -11,37 +24,606 @@ use crate::audio::AudioCommand;
 use iced::Alignment::Center;
 
 use iced::widget::{
-    Column, Row,  button, center_x, container, horizontal_space, row, slider, text, tooltip,
-    column, 
+    Column, Row, button, center_x, column, container, horizontal_space, row, scrollable, slider,
+    text, tooltip,
 };
 use iced::{Alignment, Element, Font, Length, Theme};
 use kira::sound::PlaybackState;
 use std::time::Duration;
 
 // Create a track card component
-pub fn track_card(track: &NoiseTrack, index: usize) -> Element<dragwin::Message> {
+pub fn track_card(track: &NoiseTrack, index: usize, focused: bool) -> Element<dragwin::Message> {
     let card_content = Column::new()
-        .push(track_header(track))
+        .push(track_header(track, index))
         .push(volume_slider(track, index))
-        .push(volume_display(track))
+        .push(drift_range(track))
+        .push(pan_slider(track, index))
+        .push(
+            row![
+                mix_toggle(fl!("mute"), track.muted, AudioCommand::ToggleMute(index)),
+                volume_display(track),
+                mix_toggle(fl!("solo"), track.solo, AudioCommand::ToggleSolo(index)),
+            ]
+            .align_y(Center),
+        )
         .spacing(SPACING)
         .width(Length::Fill)
         .height(Length::Fill);
 
     button(card_content)
-        .style(styles::card_button_style)
+        .style(if focused {
+            styles::focused_card_button_style
+        } else {
+            styles::card_button_style
+        })
         .on_press(dragwin::Message::Audio(AudioCommand::Play(index)))
         .into()
 }
 
-// Create the header section of a track card (icon + name)
-fn track_header(track: &NoiseTrack) -> Row<dragwin::Message> {
+// Create the header section of a track card (icon + name + favorite toggle)
+fn track_header(track: &NoiseTrack, index: usize) -> Row<dragwin::Message> {
     Row::new()
         .push(track_icon(track))
         .push(track_name(&track.name))
+        .push(favorite_button(track, index))
+        .push(details_button(track, index))
         .align_y(Alignment::Center)
 }
 
+// Button flipping the card to its filter and EQ panel, highlighted when tone shaping,
+// drift, a changed playback rate or a granular texture is active
+fn details_button(track: &NoiseTrack, index: usize) -> Element<dragwin::Message> {
+    let active = !track.tone.is_flat()
+        || !track.drift.is_off()
+        || track.playback_rate != 1.0
+        || track.source.granular().is_some();
+
+    button(text(fl!("eq")).size(10).center())
+        .width(24)
+        .height(20)
+        .padding(0)
+        .style(if active {
+            button::primary
+        } else {
+            button::text
+        })
+        .on_press(dragwin::Message::UI(dragwin::UIMessage::ToggleDetails(
+            index,
+        )))
+        .into()
+}
+
+// Back side of a track card: filter cutoffs, EQ gains and volume drift, plus the tone
+// controls of the generator track or the playback rate and granular texture of a file
+pub fn tone_panel(track: &NoiseTrack, index: usize) -> Element<dragwin::Message> {
+    let tone = track.tone;
+    let set_tone = move |tone: ToneSettings| {
+        dragwin::Message::Audio(AudioCommand::SetTone {
+            track_id: index,
+            tone,
+        })
+    };
+    let drift = track.drift;
+    let set_drift = move |drift: DriftSettings| {
+        dragwin::Message::Audio(AudioCommand::SetDrift {
+            track_id: index,
+            drift,
+        })
+    };
+
+    let cutoff = |label: String, value: f32, apply: fn(ToneSettings, f32) -> ToneSettings| {
+        tone_row(
+            label,
+            format!("{} Hz", value.round()),
+            slider(0.0..=1.0, cutoff_to_position(value), move |position| {
+                set_tone(apply(tone, position_to_cutoff(position)))
+            })
+            .step(0.01),
+        )
+    };
+    let band = |label: String, value: f32, apply: fn(ToneSettings, f32) -> ToneSettings| {
+        tone_row(
+            label,
+            format!("{value:+.0} dB"),
+            slider(-MAX_EQ_GAIN_DB..=MAX_EQ_GAIN_DB, value, move |gain| {
+                set_tone(apply(tone, gain))
+            })
+            .default(0.0)
+            .step(0.5),
+        )
+    };
+
+    let mut content = Column::new().push(
+        row![
+            track_name(&track.name),
+            button(text(fl!("done")).size(10))
+                .style(button::text)
+                .on_press(dragwin::Message::UI(dragwin::UIMessage::ToggleDetails(
+                    index
+                ))),
+        ]
+        .height(20)
+        .align_y(Center),
+    );
+    if let Some(generator) = track.source.generator() {
+        content = content.push(generator_controls(generator, index));
+    } else {
+        content = content.push(rate_row(track.playback_rate, index));
+        if track.source.file().is_some() {
+            content = content.push(granular_controls(track.source.granular(), index));
+        }
+    }
+
+    let content = content
+        .push(cutoff(fl!("low-pass"), tone.low_pass, |tone, low_pass| {
+            ToneSettings { low_pass, ..tone }
+        }))
+        .push(cutoff(
+            fl!("high-pass"),
+            tone.high_pass,
+            |tone, high_pass| ToneSettings { high_pass, ..tone },
+        ))
+        .push(band(fl!("eq-low"), tone.low, |tone, low| ToneSettings {
+            low,
+            ..tone
+        }))
+        .push(band(fl!("eq-mid"), tone.mid, |tone, mid| ToneSettings {
+            mid,
+            ..tone
+        }))
+        .push(band(fl!("eq-high"), tone.high, |tone, high| ToneSettings {
+            high,
+            ..tone
+        }))
+        .push(tone_row(
+            fl!("drift"),
+            if drift.is_off() {
+                fl!("off")
+            } else {
+                format!("±{:.0} dB", drift.depth)
+            },
+            slider(0.0..=MAX_DRIFT_DEPTH_DB, drift.depth, move |depth| {
+                set_drift(DriftSettings { depth, ..drift })
+            })
+            .default(0.0)
+            .step(0.5),
+        ))
+        .push(tone_row(
+            fl!("drift-speed"),
+            format!("{:.1}/min", drift.rate),
+            slider(MIN_DRIFT_RATE..=MAX_DRIFT_RATE, drift.rate, move |rate| {
+                set_drift(DriftSettings { rate, ..drift })
+            })
+            .default(DriftSettings::default().rate)
+            .step(0.5),
+        ))
+        .push(
+            row![
+                button(text(fl!("reset")).size(10))
+                    .style(button::text)
+                    .on_press_maybe((!tone.is_flat()).then(|| set_tone(ToneSettings::default()))),
+                horizontal_space(),
+                // Shape of the drift, clicking switches to the other one
+                button(text(drift.shape.to_string()).size(10))
+                    .style(button::text)
+                    .on_press(set_drift(DriftSettings {
+                        shape: drift.shape.toggled(),
+                        ..drift
+                    })),
+            ]
+            .align_y(Center),
+        )
+        .spacing(2)
+        .width(Length::Fill);
+
+    container(scrollable(content).height(Length::Fill))
+        .padding(8)
+        .style(|theme: &Theme| container::Style {
+            border: iced::Border {
+                color: theme.extended_palette().primary.strong.color,
+                width: 1.0,
+                radius: 8.0.into(),
+            },
+            ..container::Style::default()
+        })
+        .into()
+}
+
+// Mode, waveform and frequencies of the generator track
+fn generator_controls(
+    generator: GeneratorSettings,
+    index: usize,
+) -> Element<'static, dragwin::Message> {
+    use iced::widget::pick_list;
+
+    let set_generator = move |generator: GeneratorSettings| {
+        dragwin::Message::Audio(AudioCommand::SetGenerator {
+            track_id: index,
+            generator,
+        })
+    };
+
+    let mode = pick_list(GeneratorMode::all(), Some(generator.mode), move |mode| {
+        set_generator(GeneratorSettings { mode, ..generator })
+    })
+    .text_size(10);
+
+    // Noise has no tone, only its color to pick
+    if !generator.mode.is_tone() {
+        return row![
+            mode,
+            horizontal_space(),
+            pick_list(NoiseColor::all(), Some(generator.noise), move |noise| {
+                set_generator(GeneratorSettings { noise, ..generator })
+            })
+            .text_size(10),
+        ]
+        .align_y(Center)
+        .into();
+    }
+
+    // Beat presets, the band the current beat falls in is highlighted
+    let current = BrainwaveBand::of(generator.beat);
+    let bands = BrainwaveBand::all()
+        .iter()
+        .fold(Row::new().spacing(2), |bands, band| {
+            let label = text(format!("{} {band}", band.symbol()))
+                .size(10)
+                .shaping(text::Shaping::Advanced);
+            bands.push(
+                button(label)
+                    .style(if *band == current {
+                        button::primary
+                    } else {
+                        button::text
+                    })
+                    .padding([2, 6])
+                    .on_press(set_generator(GeneratorSettings {
+                        beat: band.beat(),
+                        ..generator
+                    })),
+            )
+        });
+
+    column![
+        row![
+            mode,
+            horizontal_space(),
+            pick_list(
+                GeneratorWaveform::all(),
+                Some(generator.waveform),
+                move |waveform| {
+                    set_generator(GeneratorSettings {
+                        waveform,
+                        ..generator
+                    })
+                }
+            )
+            .text_size(10),
+        ]
+        .align_y(Center),
+        bands,
+        tone_row(
+            fl!("carrier"),
+            format!("{} Hz", generator.carrier.round()),
+            slider(
+                MIN_CARRIER_HZ..=MAX_CARRIER_HZ,
+                generator.carrier,
+                move |carrier| {
+                    set_generator(GeneratorSettings {
+                        carrier,
+                        ..generator
+                    })
+                }
+            )
+            .default(GeneratorSettings::default().carrier)
+            .step(1.0),
+        ),
+        tone_row(
+            fl!("beat"),
+            format!("{:.1} Hz", generator.beat),
+            slider(MIN_BEAT_HZ..=MAX_BEAT_HZ, generator.beat, move |beat| {
+                set_generator(GeneratorSettings { beat, ..generator })
+            })
+            .default(GeneratorSettings::default().beat)
+            .step(0.5),
+        ),
+    ]
+    .spacing(2)
+    .into()
+}
+
+// Switch between looping the file and a granular texture, with the texture parameters
+fn granular_controls(
+    granular: Option<GranularSettings>,
+    index: usize,
+) -> Element<'static, dragwin::Message> {
+    use iced::widget::pick_list;
+
+    let set_granular = move |granular: Option<GranularSettings>| {
+        dragwin::Message::Audio(AudioCommand::SetGranular {
+            track_id: index,
+            granular,
+        })
+    };
+
+    let header = row![
+        text(fl!("granular")).size(10),
+        horizontal_space(),
+        button(
+            text(if granular.is_some() {
+                fl!("on")
+            } else {
+                fl!("off")
+            })
+            .size(10)
+        )
+        .style(if granular.is_some() {
+            button::primary
+        } else {
+            button::text
+        })
+        .padding([2, 6])
+        .on_press(set_granular(match granular {
+            Some(_) => None,
+            None => Some(GranularSettings::default()),
+        })),
+    ]
+    .spacing(5)
+    .align_y(Center);
+    let Some(granular) = granular else {
+        return header.into();
+    };
+
+    let set = move |granular: GranularSettings| set_granular(Some(granular));
+    let defaults = GranularSettings::default();
+    column![
+        header,
+        tone_row(
+            fl!("grain"),
+            format!("{:.0} ms", granular.grain_size),
+            slider(
+                MIN_GRAIN_SIZE_MS..=MAX_GRAIN_SIZE_MS,
+                granular.grain_size,
+                move |grain_size| {
+                    set(GranularSettings {
+                        grain_size,
+                        ..granular
+                    })
+                }
+            )
+            .default(defaults.grain_size)
+            .step(5.0),
+        ),
+        tone_row(
+            fl!("density"),
+            format!("{:.0}/s", granular.density),
+            slider(
+                MIN_GRAIN_DENSITY..=MAX_GRAIN_DENSITY,
+                granular.density,
+                move |density| {
+                    set(GranularSettings {
+                        density,
+                        ..granular
+                    })
+                }
+            )
+            .default(defaults.density)
+            .step(1.0),
+        ),
+        tone_row(
+            fl!("scatter"),
+            format!("{:.0}%", granular.position_jitter * 100.0),
+            slider(
+                0.0..=1.0,
+                granular.position_jitter,
+                move |position_jitter| {
+                    set(GranularSettings {
+                        position_jitter,
+                        ..granular
+                    })
+                }
+            )
+            .default(defaults.position_jitter)
+            .step(0.01),
+        ),
+        tone_row(
+            fl!("detune"),
+            format!("±{:.1} st", granular.pitch_jitter),
+            slider(
+                0.0..=MAX_PITCH_JITTER,
+                granular.pitch_jitter,
+                move |pitch_jitter| {
+                    set(GranularSettings {
+                        pitch_jitter,
+                        ..granular
+                    })
+                }
+            )
+            .default(defaults.pitch_jitter)
+            .step(0.1),
+        ),
+        row![
+            text(fl!("window")).size(10).width(50),
+            pick_list(GrainWindow::all(), Some(granular.window), move |window| {
+                set(GranularSettings { window, ..granular })
+            })
+            .text_size(10),
+        ]
+        .spacing(5)
+        .align_y(Center),
+    ]
+    .spacing(2)
+    .into()
+}
+
+// Playback rate of a recorded track, set in semitones since the pitch follows
+fn rate_row(rate: f32, index: usize) -> Element<'static, dragwin::Message> {
+    tone_row(
+        fl!("rate"),
+        format!("×{rate:.2}"),
+        slider(-12.0..=12.0, rate_to_semitones(rate), move |semitones| {
+            dragwin::Message::Audio(AudioCommand::SetPlaybackRate {
+                track_id: index,
+                rate: semitones_to_rate(semitones),
+            })
+        })
+        .default(0.0)
+        .step(0.5),
+    )
+}
+
+// A labelled slider row of the tone panel
+fn tone_row<'a>(
+    label: String,
+    value: String,
+    slider: iced::widget::Slider<'a, f32, dragwin::Message>,
+) -> Element<'a, dragwin::Message> {
+    row![
+        text(label).size(10).width(50),
+        slider.height(10.0).style(styles::volume_slider_style),
+        text(value)
+            .size(10)
+            .width(55)
+            .align_x(iced::alignment::Horizontal::Right),
+    ]
+    .spacing(5)
+    .align_y(Center)
+    .into()
+}
+
+// Star button marking a track as favorite
+fn favorite_button(track: &NoiseTrack, index: usize) -> Element<dragwin::Message> {
+    let star = if track.favorite { "★" } else { "☆" };
+
+    button(
+        text(star)
+            .size(16)
+            .shaping(text::Shaping::Advanced)
+            .style(|theme: &Theme| text::Style {
+                color: Some(theme.extended_palette().primary.base.color),
+            }),
+    )
+    .padding(2)
+    .style(button::text)
+    .on_press(dragwin::Message::UI(dragwin::UIMessage::ToggleFavorite(
+        index,
+    )))
+    .into()
+}
+
+// Create the search and filter bar shown above the track grid
+pub fn filter_bar(filter: &TrackFilter) -> Element<dragwin::Message> {
+    use iced::widget::{checkbox, text_input};
+
+    row![
+        text_input(&fl!("filter-placeholder"), &filter.query)
+            .on_input(|query| dragwin::Message::UI(dragwin::UIMessage::FilterChanged(query)))
+            .size(14)
+            .padding(6)
+            .width(Length::Fill),
+        checkbox(fl!("playing-only"), filter.playing_only)
+            .on_toggle(
+                |enabled| dragwin::Message::UI(dragwin::UIMessage::PlayingOnlyToggled(enabled))
+            )
+            .text_size(14),
+        checkbox(fl!("favorites-only"), filter.favorites_only)
+            .on_toggle(
+                |enabled| dragwin::Message::UI(dragwin::UIMessage::FavoritesOnlyToggled(enabled))
+            )
+            .text_size(14),
+    ]
+    .spacing(15)
+    .padding([0, 18])
+    .align_y(Center)
+    .into()
+}
+
+// Create the header of a category section (collapse toggle, playing count, controls)
+pub fn section_header<'a>(
+    section: &TrackSection,
+    playing: usize,
+    collapsed: bool,
+) -> Element<'a, dragwin::Message> {
+    let name = section.name().to_string();
+    let arrow = if collapsed { "▸" } else { "▾" };
+
+    let toggle = button(
+        row![
+            text(arrow).size(14).shaping(text::Shaping::Advanced),
+            text(uppercase_first(&name))
+                .size(16)
+                .shaping(text::Shaping::Advanced),
+        ]
+        .spacing(8)
+        .align_y(Center),
+    )
+    .style(button::text)
+    .on_press(dragwin::Message::UI(dragwin::UIMessage::ToggleSection(
+        name.clone(),
+    )));
+
+    row![
+        toggle,
+        text(fl!(
+            "section-playing",
+            playing = playing,
+            total = section.tracks.len()
+        ))
+        .size(12)
+        .style(styles::secondary_text_style),
+        horizontal_space(),
+        action(
+            pause_icon(),
+            text(fl!("pause-section")),
+            (playing > 0)
+                .then(|| { dragwin::Message::UI(dragwin::UIMessage::PauseSection(name.clone())) }),
+        ),
+        action(
+            stop_icon(),
+            text(fl!("stop-section")),
+            Some(dragwin::Message::UI(dragwin::UIMessage::StopSection(name))),
+        ),
+    ]
+    .spacing(10)
+    .padding([0, 18])
+    .align_y(Center)
+    .into()
+}
+
+// Dismissable message shown above the track grid
+pub fn notice_banner(message: &str) -> Element<dragwin::Message> {
+    container(
+        row![
+            text(message)
+                .size(14)
+                .style(styles::secondary_text_style)
+                .shaping(text::Shaping::Advanced),
+            horizontal_space(),
+            button(text(fl!("dismiss")).size(12))
+                .style(button::text)
+                .on_press(dragwin::Message::UI(dragwin::UIMessage::DismissNotice)),
+        ]
+        .align_y(Center),
+    )
+    .padding([4, 18])
+    .into()
+}
+
+// Shown instead of the grid when the filter hides every track
+pub fn no_matches<'a>() -> Element<'a, dragwin::Message> {
+    container(
+        text(fl!("no-matches"))
+            .size(16)
+            .style(styles::secondary_text_style),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x(Length::Fill)
+    .center_y(Length::Fill)
+    .into()
+}
+
 // Create the appropriate icon based on track state
 fn track_icon(track: &NoiseTrack) -> Element<dragwin::Message> {
     use iced::widget::container;
@@ -145,6 +727,55 @@ pub fn volume_slider(track: &NoiseTrack, index: usize) -> Element<dragwin::Messa
     .into()
 }
 
+// Thin bar under the volume slider marking the range the drift moves the volume in
+fn drift_range(track: &NoiseTrack) -> Element<dragwin::Message> {
+    if track.drift.is_off() {
+        return horizontal_space().height(2).into();
+    }
+
+    let (low, high) = track.drift.range(track.volume_level);
+    let low = db_to_percentage(low).round() as u16;
+    let high = (db_to_percentage(high).round() as u16).max(low + 1);
+    row![
+        horizontal_space().width(Length::FillPortion(low)),
+        container(horizontal_space())
+            .width(Length::FillPortion(high - low))
+            .height(2)
+            .style(|theme: &Theme| {
+                container::background(theme.extended_palette().primary.weak.color)
+            }),
+        horizontal_space().width(Length::FillPortion(100 - high.min(100))),
+    ]
+    .height(2)
+    .into()
+}
+
+// Create a stereo position slider, ctrl+click centres it again
+pub fn pan_slider(track: &NoiseTrack, index: usize) -> Element<dragwin::Message> {
+    row![
+        text(fl!("pan-left"))
+            .size(10)
+            .style(styles::secondary_text_style),
+        slider(-1.0..=1.0, track.pan, move |pan| {
+            dragwin::Message::Audio(AudioCommand::SetPan {
+                track_id: index,
+                pan,
+            })
+        })
+        .default(0.0)
+        .on_release(dragwin::Message::UI(dragwin::UIMessage::PanReleased(index)))
+        .step(0.05)
+        .height(10.0)
+        .style(styles::volume_slider_style),
+        text(fl!("pan-right"))
+            .size(10)
+            .style(styles::secondary_text_style),
+    ]
+    .spacing(5)
+    .align_y(Center)
+    .into()
+}
+
 // Create a volume percentage display
 fn volume_display(track: &NoiseTrack) -> Element<dragwin::Message> {
     text(format!("{}%", db_to_percentage(track.volume_level) as u8))
@@ -154,6 +785,25 @@ fn volume_display(track: &NoiseTrack) -> Element<dragwin::Message> {
         .into()
 }
 
+// Small mute/solo switch shown on each card, highlighted while enabled
+fn mix_toggle<'a>(
+    label: String,
+    enabled: bool,
+    command: AudioCommand,
+) -> Element<'a, dragwin::Message> {
+    button(text(label).size(10).center())
+        .width(20)
+        .height(20)
+        .padding(0)
+        .style(if enabled {
+            button::primary
+        } else {
+            button::text
+        })
+        .on_press(dragwin::Message::Audio(command))
+        .into()
+}
+
 // Create an error display component
 pub fn error_display(error: &AppError) -> Element<dragwin::Message> {
     let (icon_path, message): (&str, String) = match error {
@@ -197,7 +847,16 @@ pub fn error_display(error: &AppError) -> Element<dragwin::Message> {
 }
 
 // Create a toolbar component
-pub fn toolbar<'a>(master_volume: f32) -> Element<'a, dragwin::Message> {
+pub fn toolbar<'a>(
+    master_volume: f32,
+    active_tracks: (usize, usize),
+    focus: Option<String>,
+) -> Element<'a, dragwin::Message> {
+    // Countdown of the running focus phase, clicking it ends the session
+    let focus_button = button(text(focus.unwrap_or_else(|| fl!("focus"))).size(10))
+        .style(button::text)
+        .on_press(dragwin::Message::UI(dragwin::UIMessage::ToggleFocus));
+
     row![
         //in this case tool bar is my button
         iced::widget::Space::new(15, 10), 
@@ -221,6 +880,14 @@ pub fn toolbar<'a>(master_volume: f32) -> Element<'a, dragwin::Message> {
         ),
         action(stop_icon(), text(fl!("stop-icon")), Some(dragwin::Message::Audio(AudioCommand::StopAll))),
         iced::widget::Space::new(10, 10),
+        text(fl!(
+            "tracks-active",
+            active = active_tracks.0,
+            total = active_tracks.1
+        ))
+            .size(10)
+            .style(styles::secondary_text_style),
+        focus_button,
      
         horizontal_space(),
         row![
@@ -315,10 +982,434 @@ fn uppercase_first(data: &str) -> String {
     result
 }
 
+// Length, fade and format of an offline render of the current mix
+fn export_form<'a>(options: ExportOptions, exporting: bool) -> Element<'a, dragwin::Message> {
+    use iced::widget::{checkbox, pick_list};
+
+    let minutes = options.duration.as_secs() / 60;
+    let export_button = button(
+        text(if exporting {
+            fl!("exporting")
+        } else {
+            fl!("export-mix")
+        })
+        .size(14),
+    )
+    .style(button::primary)
+    .on_press_maybe((!exporting).then_some(dragwin::Message::UI(dragwin::UIMessage::ExportMix)));
+
+    column![
+        row![
+            text(fl!("export-length"))
+                .size(16)
+                .style(styles::secondary_text_style),
+            slider(1.0..=MAX_EXPORT_MINUTES as f32, minutes as f32, |minutes| {
+                dragwin::Message::UI(dragwin::UIMessage::ExportDurationChanged(minutes as u64))
+            })
+            .step(1.0)
+            .style(styles::volume_slider_style),
+            text(fl!("minutes-short", minutes = minutes))
+                .size(12)
+                .style(styles::secondary_text_style),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        row![
+            checkbox(fl!("export-fade"), options.fade)
+                .on_toggle(
+                    |enabled| dragwin::Message::UI(dragwin::UIMessage::ExportFadeToggled(enabled))
+                )
+                .text_size(16),
+            horizontal_space(),
+            pick_list(ExportFormat::all(), Some(options.format), |format| {
+                dragwin::Message::UI(dragwin::UIMessage::ExportFormatChanged(format))
+            }),
+            export_button,
+        ]
+        .spacing(10)
+        .align_y(Center),
+    ]
+    .spacing(10)
+    .into()
+}
+
+// Wake-up alarms, and the form to add one
+fn alarm_editor<'a>(
+    alarms: &'a [Alarm],
+    draft: &'a AlarmDraft,
+    presets: &'a [Preset],
+) -> Element<'a, dragwin::Message> {
+    use iced::widget::{checkbox, pick_list, text_input};
+
+    let list = alarms.iter().enumerate().map(|(index, alarm)| {
+        row![
+            checkbox(alarm.time.format("%H:%M").to_string(), alarm.enabled)
+                .on_toggle(move |enabled| {
+                    dragwin::Message::UI(dragwin::UIMessage::AlarmToggled(index, enabled))
+                })
+                .text_size(16),
+            text(fl!(
+                "alarm-summary",
+                days = alarm.days_label(),
+                preset = alarm.preset.as_str(),
+                fade = alarm.fade_minutes
+            ))
+            .size(12)
+            .style(styles::secondary_text_style),
+            horizontal_space(),
+            button(text(fl!("remove")).size(12))
+                .style(button::text)
+                .on_press(dragwin::Message::UI(dragwin::UIMessage::RemoveAlarm(index))),
+        ]
+        .spacing(10)
+        .align_y(Center)
+        .into()
+    });
+
+    let preset_names: Vec<String> = presets.iter().map(|preset| preset.name.clone()).collect();
+    let days = WEEKDAYS.iter().map(|day| {
+        let selected = draft.days.contains(day);
+        button(text(day.to_string()).size(10).center())
+            .width(32)
+            .padding(2)
+            .style(if selected {
+                button::primary
+            } else {
+                button::text
+            })
+            .on_press(dragwin::Message::UI(dragwin::UIMessage::AlarmDayToggled(
+                *day,
+            )))
+            .into()
+    });
+
+    column![
+        text(fl!("alarms"))
+            .size(16)
+            .style(styles::secondary_text_style),
+        Column::with_children(list).spacing(5),
+        row![
+            text_input("07:00", &draft.time)
+                .on_input(|time| dragwin::Message::UI(dragwin::UIMessage::AlarmTimeChanged(time)))
+                .width(60),
+            pick_list(preset_names, draft.preset.clone(), |preset| {
+                dragwin::Message::UI(dragwin::UIMessage::AlarmPresetChanged(preset))
+            })
+            .placeholder(fl!("preset")),
+            button(text(fl!("add-alarm")).size(14))
+                .style(button::primary)
+                .on_press_maybe(
+                    draft
+                        .alarm()
+                        .map(|_| dragwin::Message::UI(dragwin::UIMessage::AddAlarm))
+                ),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        Row::with_children(days).spacing(4),
+        row![
+            text(fl!("alarm-fade"))
+                .size(14)
+                .style(styles::secondary_text_style),
+            slider(
+                0.0..=MAX_ALARM_FADE_MINUTES as f32,
+                draft.fade_minutes as f32,
+                |minutes| {
+                    dragwin::Message::UI(dragwin::UIMessage::AlarmFadeChanged(minutes as u32))
+                }
+            )
+            .step(1.0)
+            .style(styles::volume_slider_style),
+            text(fl!("minutes-short", minutes = draft.fade_minutes))
+                .size(12)
+                .style(styles::secondary_text_style),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        text(fl!("alarm-no-days"))
+            .size(12)
+            .style(styles::secondary_text_style),
+    ]
+    .spacing(10)
+    .into()
+}
+
+// 24 hour bar showing which preset plays when
+fn schedule_timeline(schedule: &Schedule) -> Element<dragwin::Message> {
+    let segments = schedule
+        .segments()
+        .into_iter()
+        .enumerate()
+        .map(|(index, (minutes, entry))| {
+            container(
+                text(&entry.preset)
+                    .size(10)
+                    .shaping(text::Shaping::Advanced)
+                    .wrapping(text::Wrapping::None),
+            )
+            .padding([2, 4])
+            .clip(true)
+            .width(Length::FillPortion(minutes as u16))
+            .style(if index % 2 == 0 {
+                container::rounded_box
+            } else {
+                container::bordered_box
+            })
+            .into()
+        });
+
+    column![
+        Row::with_children(segments).height(22),
+        row![
+            text("00:00").size(10).style(styles::secondary_text_style),
+            horizontal_space(),
+            text("12:00").size(10).style(styles::secondary_text_style),
+            horizontal_space(),
+            text("24:00").size(10).style(styles::secondary_text_style),
+        ],
+    ]
+    .spacing(2)
+    .into()
+}
+
+// Presets started at times of day, and the form to add one
+fn schedule_editor<'a>(
+    schedule: &'a Schedule,
+    draft: &'a ScheduleDraft,
+    presets: &'a [Preset],
+) -> Element<'a, dragwin::Message> {
+    use iced::widget::{checkbox, pick_list, text_input};
+
+    let entries = schedule.entries.iter().enumerate().map(|(index, entry)| {
+        let time = entry.time.format("%H:%M").to_string();
+        let label = match entry.sleep_minutes {
+            Some(minutes) => fl!(
+                "schedule-entry-sleep",
+                time = time,
+                preset = entry.preset.as_str(),
+                minutes = minutes
+            ),
+            None => format!("{time} {}", entry.preset),
+        };
+        row![
+            text(label).size(14).shaping(text::Shaping::Advanced),
+            horizontal_space(),
+            button(text(fl!("remove")).size(12))
+                .style(button::text)
+                .on_press(dragwin::Message::UI(
+                    dragwin::UIMessage::RemoveScheduleEntry(index)
+                )),
+        ]
+        .align_y(Center)
+        .into()
+    });
+
+    let preset_names: Vec<String> = presets.iter().map(|preset| preset.name.clone()).collect();
+    let crossfade = schedule.crossfade_seconds;
+
+    column![
+        checkbox(fl!("schedule-enabled"), schedule.enabled)
+            .on_toggle(|enabled| dragwin::Message::UI(dragwin::UIMessage::ScheduleToggled(enabled)))
+            .text_size(16),
+        schedule_timeline(schedule),
+        Column::with_children(entries).spacing(5),
+        row![
+            text_input("08:00", &draft.time)
+                .on_input(
+                    |time| dragwin::Message::UI(dragwin::UIMessage::ScheduleTimeChanged(time))
+                )
+                .width(60),
+            pick_list(preset_names, draft.preset.clone(), |preset| {
+                dragwin::Message::UI(dragwin::UIMessage::SchedulePresetChanged(preset))
+            })
+            .placeholder(fl!("preset")),
+            button(text(fl!("add")).size(14))
+                .style(button::primary)
+                .on_press_maybe(
+                    draft
+                        .entry()
+                        .map(|_| dragwin::Message::UI(dragwin::UIMessage::AddScheduleEntry))
+                ),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        row![
+            text(fl!("schedule-sleep"))
+                .size(14)
+                .style(styles::secondary_text_style),
+            slider(
+                0.0..=MAX_SCHEDULE_SLEEP_MINUTES as f32,
+                draft.sleep_minutes as f32,
+                |minutes| {
+                    dragwin::Message::UI(dragwin::UIMessage::ScheduleSleepChanged(minutes as u64))
+                }
+            )
+            .step(5.0)
+            .style(styles::volume_slider_style),
+            text(if draft.sleep_minutes == 0 {
+                fl!("never")
+            } else {
+                fl!("minutes-short", minutes = draft.sleep_minutes)
+            })
+            .size(12)
+            .style(styles::secondary_text_style),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        row![
+            text(fl!("crossfade"))
+                .size(14)
+                .style(styles::secondary_text_style),
+            slider(
+                0.0..=MAX_CROSSFADE_SECONDS as f32,
+                crossfade as f32,
+                |seconds| {
+                    dragwin::Message::UI(dragwin::UIMessage::ScheduleCrossfadeChanged(
+                        seconds as u64,
+                    ))
+                }
+            )
+            .step(5.0)
+            .style(styles::volume_slider_style),
+            text(format!("{crossfade} s"))
+                .size(12)
+                .style(styles::secondary_text_style),
+        ]
+        .spacing(10)
+        .align_y(Center),
+    ]
+    .spacing(10)
+    .into()
+}
+
+// Lengths and presets of the focus phases, and the completed cycles
+fn focus_form<'a>(
+    settings: &FocusSettings,
+    history: &FocusHistory,
+    presets: &'a [Preset],
+) -> Element<'a, dragwin::Message> {
+    use iced::widget::{checkbox, pick_list};
+
+    let preset_names: Vec<String> = presets.iter().map(|preset| preset.name.clone()).collect();
+    let changed = |settings: FocusSettings| {
+        dragwin::Message::UI(dragwin::UIMessage::FocusSettingsChanged(settings))
+    };
+    let today = chrono::Local::now().date_naive();
+
+    let work = settings.clone();
+    let break_ = settings.clone();
+    let work_preset = settings.clone();
+    let break_preset = settings.clone();
+    let chime = settings.clone();
+
+    column![
+        text(fl!("focus-sessions"))
+            .size(16)
+            .style(styles::secondary_text_style),
+        row![
+            text(fl!("focus-work-length"))
+                .size(14)
+                .style(styles::secondary_text_style),
+            slider(
+                1.0..=MAX_FOCUS_MINUTES as f32,
+                settings.work_minutes as f32,
+                move |minutes| {
+                    changed(FocusSettings {
+                        work_minutes: minutes as u32,
+                        ..work.clone()
+                    })
+                }
+            )
+            .step(1.0)
+            .style(styles::volume_slider_style),
+            text(fl!("minutes-short", minutes = settings.work_minutes))
+                .size(12)
+                .style(styles::secondary_text_style),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        row![
+            text(fl!("focus-break-length"))
+                .size(14)
+                .style(styles::secondary_text_style),
+            slider(
+                1.0..=MAX_FOCUS_MINUTES as f32,
+                settings.break_minutes as f32,
+                move |minutes| {
+                    changed(FocusSettings {
+                        break_minutes: minutes as u32,
+                        ..break_.clone()
+                    })
+                }
+            )
+            .step(1.0)
+            .style(styles::volume_slider_style),
+            text(fl!("minutes-short", minutes = settings.break_minutes))
+                .size(12)
+                .style(styles::secondary_text_style),
+        ]
+        .spacing(10)
+        .align_y(Center),
+        row![
+            pick_list(
+                preset_names.clone(),
+                settings.work_preset.clone(),
+                move |preset| {
+                    changed(FocusSettings {
+                        work_preset: Some(preset),
+                        ..work_preset.clone()
+                    })
+                }
+            )
+            .placeholder(fl!("focus-work-preset")),
+            pick_list(preset_names, settings.break_preset.clone(), move |preset| {
+                changed(FocusSettings {
+                    break_preset: Some(preset),
+                    ..break_preset.clone()
+                })
+            })
+            .placeholder(fl!("focus-break-preset")),
+        ]
+        .spacing(10),
+        checkbox(fl!("focus-chime"), settings.chime)
+            .on_toggle(move |enabled| {
+                changed(FocusSettings {
+                    chime: enabled,
+                    ..chime.clone()
+                })
+            })
+            .text_size(16),
+        text(fl!(
+            "focus-cycles",
+            today = history.cycles_on(today),
+            total = history.total()
+        ))
+        .size(12)
+        .style(styles::secondary_text_style),
+    ]
+    .spacing(10)
+    .into()
+}
+
 // Create settings view with theme selection
-pub fn settings_view<'a>(current_theme: &crate::models::AppTheme) -> Element<'a, dragwin::Message> {
+pub fn settings_view<'a>(
+    current_theme: &crate::models::AppTheme,
+    close_to_tray: bool,
+    limiter: LimiterSettings,
+    normalize_audio: bool,
+    concurrency_policy: ConcurrencyPolicy,
+    export_options: ExportOptions,
+    exporting: bool,
+    alarms: &'a [Alarm],
+    alarm_draft: &'a AlarmDraft,
+    schedule: &'a Schedule,
+    schedule_draft: &'a ScheduleDraft,
+    focus_settings: &FocusSettings,
+    focus_history: &FocusHistory,
+    presets: &'a [Preset],
+) -> Element<'a, dragwin::Message> {
     use crate::models::AppTheme;
-    use iced::widget::{column, pick_list, text};
+    use iced::widget::{checkbox, column, pick_list, text};
 
     let theme_picker = pick_list(AppTheme::all(), Some(*current_theme), |theme| {
         dragwin::Message::UI(dragwin::UIMessage::ThemeChanged(theme))
@@ -326,32 +1417,92 @@ pub fn settings_view<'a>(current_theme: &crate::models::AppTheme) -> Element<'a,
 
     let back_button = action(back_icon(), text(fl!("back")).shaping(text::Shaping::Advanced), Some(dragwin::Message::UI(dragwin::UIMessage::BackToPlayer)));
 
-    container(
-        column![
-            text("Settings")
-                .size(24)
-                .style(styles::secondary_text_style)
-                .align_x(iced::alignment::Horizontal::Center),
-            row![
-                text("Theme:")
-                    .size(16)
+    // Scrolls once the alarms make the settings taller than the window
+    scrollable(
+        container(
+            column![
+                text("Settings")
+                    .size(24)
                     .style(styles::secondary_text_style)
-                    .align_x(iced::alignment::Horizontal::Left),
-                theme_picker
+                    .align_x(iced::alignment::Horizontal::Center),
+                row![
+                    text("Theme:")
+                        .size(16)
+                        .style(styles::secondary_text_style)
+                        .align_x(iced::alignment::Horizontal::Left),
+                    theme_picker
+                ]
+                .spacing(50)
+                .align_y(Center),
+                checkbox(fl!("close-to-tray"), close_to_tray)
+                    .on_toggle(|enabled| dragwin::Message::UI(
+                        dragwin::UIMessage::CloseToTrayToggled(enabled)
+                    ))
+                    .text_size(16),
+                row![
+                    text(fl!("concurrency-policy"))
+                        .size(16)
+                        .style(styles::secondary_text_style),
+                    pick_list(
+                        ConcurrencyPolicy::all(),
+                        Some(concurrency_policy),
+                        |policy| {
+                            dragwin::Message::Audio(AudioCommand::SetConcurrencyPolicy(policy))
+                        }
+                    ),
+                ]
+                .spacing(10)
+                .align_y(Center),
+                checkbox(fl!("normalize"), normalize_audio)
+                    .on_toggle(
+                        |enabled| dragwin::Message::Audio(AudioCommand::SetNormalize(enabled))
+                    )
+                    .text_size(16),
+                checkbox(fl!("limiter"), limiter.enabled)
+                    .on_toggle(move |enabled| {
+                        dragwin::Message::Audio(AudioCommand::SetLimiter(LimiterSettings {
+                            enabled,
+                            ..limiter
+                        }))
+                    })
+                    .text_size(16),
+                row![
+                    text(fl!("limiter-threshold"))
+                        .size(16)
+                        .style(styles::secondary_text_style),
+                    slider(
+                        MIN_LIMITER_THRESHOLD_DB..=0.0,
+                        limiter.threshold,
+                        move |threshold| {
+                            dragwin::Message::Audio(AudioCommand::SetLimiter(LimiterSettings {
+                                threshold,
+                                ..limiter
+                            }))
+                        }
+                    )
+                    .step(0.5)
+                    .style(styles::volume_slider_style),
+                    text(format!("{:.1} dB", limiter.threshold))
+                        .size(12)
+                        .style(styles::secondary_text_style),
+                ]
+                .spacing(10)
+                .align_y(Center),
+                export_form(export_options, exporting),
+                alarm_editor(alarms, alarm_draft, presets),
+                schedule_editor(schedule, schedule_draft, presets),
+                focus_form(focus_settings, focus_history, presets),
+                back_button,
             ]
-            .spacing(50)
-            .align_y(Center),
-            back_button,
-        ]
-        .spacing(20)
-        .padding(20)
-        .align_x(Alignment::Center)
-        .max_width(400),
+            .spacing(20)
+            .padding(20)
+            .align_x(Alignment::Center)
+            .max_width(400),
+        )
+        .width(Length::Fill)
+        .center_x(Length::Fill),
     )
-    .width(Length::Fill)
     .height(Length::Fill)
-    .center_x(Length::Fill)
-    .center_y(Length::Fill)
     .into()
 }
 
@@ -374,8 +1525,55 @@ mod tests {
         let track = NoiseTrack::new("test_track".to_string(), PathBuf::from("/test/path.mp3"));
 
         // Test that components can be created without panicking
-        let _card = track_card(&track, 0);
+        let _card = track_card(&track, 0, false);
+        let _focused = track_card(&track, 0, true);
+        let _mute = mix_toggle(fl!("mute"), true, AudioCommand::ToggleMute(0));
         let _slider = volume_slider(&track, 0);
-        let _header = track_header(&track);
+        let _pan = pan_slider(&track, 0);
+        let _tone = tone_panel(&track, 0);
+        let _generator = tone_panel(&NoiseTrack::generator(GeneratorSettings::default()), 0);
+        let _granular = tone_panel(
+            &NoiseTrack {
+                source: track
+                    .source
+                    .clone()
+                    .with_granular(Some(GranularSettings::default())),
+                ..track.clone()
+            },
+            0,
+        );
+        let mut drifting = track.clone();
+        drifting.drift.depth = 6.0;
+        let _range = drift_range(&drifting);
+        let _full = drift_range(&NoiseTrack {
+            volume_level: 0.0,
+            ..drifting
+        });
+        let _settings = settings_view(
+            &crate::models::AppTheme::default(),
+            false,
+            LimiterSettings::default(),
+            false,
+            ConcurrencyPolicy::default(),
+            ExportOptions::default(),
+            true,
+            &[],
+            &AlarmDraft::default(),
+            &Schedule::default(),
+            &ScheduleDraft::default(),
+            &FocusSettings::default(),
+            &FocusHistory::default(),
+            &[],
+        );
+        let _notice = notice_banner("Only 16 tracks can play at the same time");
+        let _header = track_header(&track, 0);
+        let _filter = filter_bar(&TrackFilter::default());
+
+        let section = TrackSection {
+            category: Some("Nature".to_string()),
+            tracks: vec![0],
+        };
+        let _expanded = section_header(&section, 1, false);
+        let _collapsed = section_header(&section, 0, true);
     }
 }
@@ -55,6 +55,20 @@ pub fn card_button_style(theme: &Theme, status: button::Status) -> button::Style
     }
 }
 
+// Style function for the track card selected with the keyboard
+pub fn focused_card_button_style(theme: &Theme, status: button::Status) -> button::Style {
+    let style = card_button_style(theme, status);
+
+    button::Style {
+        border: Border {
+            color: theme.extended_palette().primary.strong.color,
+            width: 2.0,
+            ..style.border
+        },
+        ..style
+    }
+}
+
 pub fn loader_running_style(theme: &Theme) -> sine_wave_loading::Style {
     let palette = theme.extended_palette();
     sine_wave_loading::Style {
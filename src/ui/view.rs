@@ -1,6 +1,9 @@
 use crate::app::{CosmicNoise, Message};
-use crate::models::{NoiseTrack, View};
-use crate::ui::components::{empty_state, error_display, settings_view, track_card};
+use crate::models::{TrackSection, View};
+use crate::ui::components::{
+    empty_state, error_display, filter_bar, no_matches, notice_banner, section_header,
+    settings_view, tone_panel, track_card,
+};
 use crate::utils::dragwin;
 
 use iced::Element;
@@ -24,30 +27,78 @@ fn content_area(app: &CosmicNoise) -> Element<dragwin::Message> {
                 return empty_state();
             }
 
-            // Show tracks grid
-            tracks_grid(&app.track_list)
+            // Show the filter bar above the tracks grid
+            column![]
+                .push_maybe(app.notice.as_deref().map(notice_banner))
+                .push(filter_bar(&app.filter))
+                .push(track_sections(app))
+                .spacing(10)
+                .into()
         }
-        View::Settings => settings_view(&app.current_theme),
+        View::Settings => settings_view(
+            &app.current_theme,
+            app.close_to_tray,
+            app.audio_system.limiter(),
+            app.audio_system.normalize_audio(),
+            app.audio_system.concurrency_policy(),
+            app.export_options,
+            app.exporting,
+            &app.alarms,
+            &app.alarm_draft,
+            &app.schedule,
+            &app.schedule_draft,
+            &app.focus_settings,
+            &app.focus_history,
+            &app.presets,
+        ),
     }
 }
 
-fn tracks_grid(tracks: &[NoiseTrack]) -> Element<dragwin::Message> {
-    let track_elements: Vec<Element<dragwin::Message>> = tracks
+// Group the tracks into collapsible category sections. Without any categories
+// the tracks are shown as a single grid, like before.
+fn track_sections(app: &CosmicNoise) -> Element<dragwin::Message> {
+    let sections = app.sections();
+    if sections.is_empty() {
+        return no_matches();
+    }
+
+    let content: Element<dragwin::Message> = if TrackSection::is_grouped(&sections) {
+        let mut content = column![].spacing(10);
+        for section in &sections {
+            let collapsed = app.is_collapsed(section);
+            let playing = section.playing_count(&app.track_list);
+            content = content.push(section_header(section, playing, collapsed));
+            if !collapsed {
+                content = content.push(tracks_grid(app, &section.tracks));
+            }
+        }
+        content.into()
+    } else {
+        tracks_grid(app, &sections[0].tracks)
+    };
+
+    container(scrollable(row![content].push(Space::new(18, 1)))).into()
+}
+
+// Cards keep their index into the full track list so commands target the right track
+fn tracks_grid<'a>(app: &'a CosmicNoise, indices: &[usize]) -> Element<'a, dragwin::Message> {
+    let track_elements: Vec<Element<dragwin::Message>> = indices
         .iter()
-        .enumerate()
-        .map(|(index, track)| track_card(track, index))
+        .map(|&index| {
+            let track = &app.track_list[index];
+            if app.details_track == Some(index) {
+                tone_panel(track, index)
+            } else {
+                track_card(track, index, app.focused_track == Some(index))
+            }
+        })
         .collect();
 
-    container(scrollable(
-        row![
-            grid(track_elements)
-                .spacing(5)
-                .height(iced::widget::grid::aspect_ratio(200, 150))
-                .fluid(210)
-        ]
-        .push(Space::new(18, 1)),
-    ))
-    .into()
+    grid(track_elements)
+        .spacing(5)
+        .height(iced::widget::grid::aspect_ratio(200, 150))
+        .fluid(210)
+        .into()
 }
 
 #[cfg(test)]
@@ -64,6 +115,7 @@ mod tests {
             error: None,
             current_view: View::default(),
             current_theme: crate::config::ConfigManager::load_theme(),
+            ..CosmicNoise::default()
         }
     }
 
@@ -84,4 +136,31 @@ mod tests {
         let _view = main_view(&app);
         // Test passes if no panic occurs
     }
+
+    #[test]
+    fn test_main_view_with_sections() {
+        let mut app = create_test_app();
+        let mut rain = NoiseTrack::new("rain".to_string(), PathBuf::from("/test/Weather/rain.ogg"));
+        rain.category = Some("Weather".to_string());
+        app.track_list = vec![
+            rain,
+            NoiseTrack::new("track2".to_string(), PathBuf::from("/test/track2.mp3")),
+        ];
+        app.collapsed_sections = vec!["Weather".to_string()];
+        app.details_track = Some(1);
+        app.notice = Some("Only 16 tracks can play at the same time".to_string());
+        let _view = main_view(&app);
+        assert_eq!(app.visible_tracks(), vec![1]);
+    }
+
+    #[test]
+    fn test_main_view_filtered_out() {
+        let mut app = create_test_app();
+        app.track_list = vec![NoiseTrack::new(
+            "track1".to_string(),
+            PathBuf::from("/test/track1.mp3"),
+        )];
+        app.filter.query = "nothing".to_string();
+        let _view = main_view(&app);
+    }
 }
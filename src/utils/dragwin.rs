@@ -11,6 +11,15 @@ use iced::{
     window::{self, drag_resize},
 };
 
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Weekday;
+
+use crate::errors::AppError;
+use crate::export::ExportFormat;
+use crate::fl;
+use crate::models::FocusSettings;
 use crate::{CosmicNoise, audio::AudioCommand, ui::components::toolbar};
 
 // Window management messages for drag, resize, maximize, minimize, close
@@ -36,6 +45,39 @@ pub enum UIMessage {
     Settings,
     BackToPlayer,
     ThemeChanged(crate::models::AppTheme),
+    CloseToTrayToggled(bool),
+    FilterChanged(String),
+    PlayingOnlyToggled(bool),
+    FavoritesOnlyToggled(bool),
+    ToggleFavorite(usize),
+    ToggleDetails(usize),
+    // A pan slider was let go, the position is saved then
+    PanReleased(usize),
+    ToggleSection(String),
+    DismissNotice,
+    PauseSection(String),
+    StopSection(String),
+    ExportDurationChanged(u64),
+    ExportFadeToggled(bool),
+    ExportFormatChanged(ExportFormat),
+    ExportMix,
+    ExportFinished(Result<PathBuf, AppError>),
+    AlarmTimeChanged(String),
+    AlarmPresetChanged(String),
+    AlarmDayToggled(Weekday),
+    AlarmFadeChanged(u32),
+    AddAlarm,
+    AlarmToggled(usize, bool),
+    RemoveAlarm(usize),
+    ScheduleToggled(bool),
+    ScheduleCrossfadeChanged(u64),
+    ScheduleTimeChanged(String),
+    SchedulePresetChanged(String),
+    ScheduleSleepChanged(u64),
+    AddScheduleEntry,
+    RemoveScheduleEntry(usize),
+    ToggleFocus,
+    FocusSettingsChanged(FocusSettings),
 }
 
 // Combined message type that can handle all three message types
@@ -85,13 +127,25 @@ pub fn update(message: Message, cnoise: &mut CosmicNoise) -> Task<Message> {
             WindowMessage::SouthEast => window::get_latest()
                 .and_then(|f| drag_resize(f, window::Direction::SouthEast))
                 .map(Message::Window),
+            // Keep playing in the background when a tray icon can bring the window back
+            WindowMessage::Close if cnoise.close_to_tray && cnoise.tray.is_some() => {
+                cnoise.hide_window()
+            }
             WindowMessage::Close => window::get_latest()
                 .and_then(window::close)
                 .map(Message::Window),
         },
         Message::Audio(audio_cmd) => {
+            let normalize_enabled = matches!(audio_cmd, AudioCommand::SetNormalize(true));
             cnoise.process_audio_command(audio_cmd);
-            Task::none()
+
+            // Measure the tracks that were never analyzed once normalization is turned on
+            match cnoise.loudness_analysis() {
+                Some(analysis) if normalize_enabled => Task::perform(analysis, |measured| {
+                    Message::Audio(AudioCommand::SetLoudness(measured))
+                }),
+                _ => Task::none(),
+            }
         }
         Message::UI(ui_msg) => {
             match ui_msg {
@@ -115,6 +169,159 @@ pub fn update(message: Message, cnoise: &mut CosmicNoise) -> Task<Message> {
                         log::info!("Theme saved to configuration: {theme}");
                     }
                 }
+                UIMessage::CloseToTrayToggled(enabled) => {
+                    cnoise.close_to_tray = enabled;
+
+                    if let Err(e) = crate::config::ConfigManager::save_close_to_tray(enabled) {
+                        log::error!("Failed to save close-to-tray setting: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::FilterChanged(query) => {
+                    cnoise.filter.query = query;
+                }
+                UIMessage::PlayingOnlyToggled(enabled) => {
+                    cnoise.filter.playing_only = enabled;
+                }
+                UIMessage::FavoritesOnlyToggled(enabled) => {
+                    cnoise.filter.favorites_only = enabled;
+                }
+                UIMessage::ToggleFavorite(track_id) => {
+                    if let Err(e) = cnoise.toggle_favorite(track_id) {
+                        log::error!("Failed to save favorites: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::ToggleDetails(track_id) => {
+                    cnoise.details_track =
+                        (cnoise.details_track != Some(track_id)).then_some(track_id);
+                }
+                UIMessage::PanReleased(track_id) => {
+                    if let Some(track) = cnoise.track_list.get(track_id) {
+                        if let Err(e) =
+                            crate::config::ConfigManager::save_pan(&track.name, track.pan)
+                        {
+                            log::error!("Failed to save track pan to configuration: {e}");
+                        }
+                    }
+                }
+                UIMessage::DismissNotice => {
+                    cnoise.notice = None;
+                }
+                UIMessage::ToggleSection(name) => {
+                    if let Err(e) = cnoise.toggle_section(&name) {
+                        log::error!("Failed to save collapsed sections: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::PauseSection(name) => {
+                    cnoise.command_section(&name, AudioCommand::Pause);
+                }
+                UIMessage::StopSection(name) => {
+                    cnoise.command_section(&name, AudioCommand::Stop);
+                }
+                UIMessage::ExportDurationChanged(minutes) => {
+                    cnoise.export_options.duration = Duration::from_secs(minutes * 60);
+                }
+                UIMessage::ExportFadeToggled(enabled) => {
+                    cnoise.export_options.fade = enabled;
+                }
+                UIMessage::ExportFormatChanged(format) => {
+                    cnoise.export_options.format = format;
+                }
+                UIMessage::ExportMix => {
+                    if let Some(job) = cnoise.export_job() {
+                        return Task::perform(crate::export::export(job), |result| {
+                            Message::UI(UIMessage::ExportFinished(result))
+                        });
+                    }
+                }
+                UIMessage::AlarmTimeChanged(time) => {
+                    cnoise.alarm_draft.time = time;
+                }
+                UIMessage::AlarmPresetChanged(preset) => {
+                    cnoise.alarm_draft.preset = Some(preset);
+                }
+                UIMessage::AlarmDayToggled(day) => {
+                    cnoise.alarm_draft.toggle_day(day);
+                }
+                UIMessage::AlarmFadeChanged(minutes) => {
+                    cnoise.alarm_draft.fade_minutes = minutes;
+                }
+                UIMessage::AddAlarm => {
+                    if let Err(e) = cnoise.add_alarm() {
+                        log::error!("Failed to save alarms: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::AlarmToggled(index, enabled) => {
+                    if let Err(e) = cnoise.set_alarm_enabled(index, enabled) {
+                        log::error!("Failed to save alarms: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::RemoveAlarm(index) => {
+                    if let Err(e) = cnoise.remove_alarm(index) {
+                        log::error!("Failed to save alarms: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::ScheduleToggled(enabled) => {
+                    cnoise.schedule.enabled = enabled;
+                    if let Err(e) = crate::config::ConfigManager::save_schedule(&cnoise.schedule) {
+                        log::error!("Failed to save schedule: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::ScheduleCrossfadeChanged(seconds) => {
+                    cnoise.schedule.crossfade_seconds = seconds;
+                    if let Err(e) = crate::config::ConfigManager::save_schedule(&cnoise.schedule) {
+                        log::error!("Failed to save schedule: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::ScheduleTimeChanged(time) => {
+                    cnoise.schedule_draft.time = time;
+                }
+                UIMessage::SchedulePresetChanged(preset) => {
+                    cnoise.schedule_draft.preset = Some(preset);
+                }
+                UIMessage::ScheduleSleepChanged(minutes) => {
+                    cnoise.schedule_draft.sleep_minutes = minutes;
+                }
+                UIMessage::AddScheduleEntry => {
+                    if let Err(e) = cnoise.add_schedule_entry() {
+                        log::error!("Failed to save schedule: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::RemoveScheduleEntry(index) => {
+                    if let Err(e) = cnoise.remove_schedule_entry(index) {
+                        log::error!("Failed to save schedule: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::ToggleFocus => {
+                    cnoise.toggle_focus();
+                }
+                UIMessage::FocusSettingsChanged(settings) => {
+                    cnoise.focus_settings = settings;
+                    if let Err(e) = crate::config::ConfigManager::save_focus(&cnoise.focus_settings)
+                    {
+                        log::error!("Failed to save focus settings: {e}");
+                        cnoise.error = Some(e);
+                    }
+                }
+                UIMessage::ExportFinished(result) => {
+                    cnoise.exporting = false;
+                    cnoise.notice = Some(match result {
+                        Ok(path) => fl!("export-done", path = path.display().to_string()),
+                        Err(e) => {
+                            log::error!("Failed to export mix: {e}");
+                            e.to_string()
+                        }
+                    });
+                }
             }
             Task::none()
         }
@@ -123,11 +330,18 @@ pub fn update(message: Message, cnoise: &mut CosmicNoise) -> Task<Message> {
 
 pub fn view<'a>(content: Element<'a, Message>, cnoise: &CosmicNoise) -> Element<'a, Message> {
     let master_volume = cnoise.audio_system.master_volume();
+    let active_tracks = (
+        cnoise.audio_system.active_tracks(),
+        cnoise.audio_system.max_concurrent_tracks(),
+    );
+    let focus = cnoise
+        .focus
+        .map(|session| session.label(std::time::Instant::now()));
 
     let base = iced::widget::container(
         iced::widget::column![
             mouse_area(
-                iced::widget::container(toolbar(master_volume))
+                iced::widget::container(toolbar(master_volume, active_tracks, focus))
                     .align_y(Center)
                     .width(Fill)
                     .height(40)
@@ -1,10 +1,15 @@
 use std::{
+    collections::HashSet,
     ffi::OsStr,
     path::{Path, PathBuf},
 };
 
-use crate::errors::{AppError, FileSystemError};
-use crate::models::{NoiseTrack, SOUND_DIRECTORY, SUPPORTED_EXTENSIONS};
+use crate::errors::{AppError, AudioError, FileSystemError};
+use crate::models::{
+    EVENT_FILE, EventSidecar, NoiseTrack, SIDECAR_EXTENSION, SOUND_DIRECTORY, SUPPORTED_EXTENSIONS,
+    SoundSource, TrackSidecar, VARIATIONS_FILE, VariationSidecar,
+};
+use serde::de::DeserializeOwned;
 
 pub fn get_stem(name: &Path) -> String {
     log::warn!("loading path {}", name.to_string_lossy());
@@ -21,58 +26,15 @@ pub async fn load_data() -> Result<Vec<NoiseTrack>, AppError> {
     let mut seen = std::collections::HashSet::new();
     let mut any_dir_exists = false;
 
-    // Then check user data dir
-    if let Some(data_path) = data_dir_exists() {
-        if data_path.exists() {
-            any_dir_exists = true;
-            for entry in walkdir::WalkDir::new(&data_path)
-                .max_depth(1)
-                .follow_links(false)
-                .into_iter()
-            {
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(_) => return Err(AppError::FileSystem(FileSystemError::DirectoryReadError)),
-                };
-                let path = entry.path();
-                if path.is_file() {
-                    if !path.has_extension(SUPPORTED_EXTENSIONS) {
-                        return Err(AppError::FileSystem(FileSystemError::InvalidFileFormat));
-                    }
-                    let name = get_stem(path);
-                    if seen.insert(name.clone()) {
-                        tracks.push(NoiseTrack::new(name, path.to_path_buf()));
-                    }
-                }
-            }
-        }
-    }
-    // Then check user config dir
-    if let Some(config_path) = config_dir_exists() {
-        if config_path.exists() {
-            any_dir_exists = true;
-            for entry in walkdir::WalkDir::new(&config_path)
-                .max_depth(1)
-                .follow_links(false)
-                .into_iter()
-            {
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(_) => return Err(AppError::FileSystem(FileSystemError::DirectoryReadError)),
-                };
-                let path = entry.path();
-                if path.is_file() {
-                    if !path.has_extension(SUPPORTED_EXTENSIONS) {
-                        return Err(AppError::FileSystem(FileSystemError::InvalidFileFormat));
-                    }
-                    let name = get_stem(path);
-                    if seen.insert(name.clone()) {
-                        tracks.push(NoiseTrack::new(name, path.to_path_buf()));
-                    }
-                }
-            }
-        }
+    // Check user data dir first, then user config dir
+    for sound_dir in [data_dir_exists(), config_dir_exists()]
+        .into_iter()
+        .flatten()
+    {
+        any_dir_exists = true;
+        scan_directory(&sound_dir, &mut tracks, &mut seen)?;
     }
+
     if tracks.is_empty() {
         if any_dir_exists {
             Ok(tracks)
@@ -84,6 +46,131 @@ pub async fn load_data() -> Result<Vec<NoiseTrack>, AppError> {
     }
 }
 
+// Collect the tracks of a sound directory. Files in a subdirectory are put in a
+// category named after it, e.g. `sounds/Nature/birds.ogg`. A directory with an
+// `event.toml` file is a single event track playing its files as one-shot clips,
+// one with a `variations.toml` file a track rotating through its files.
+fn scan_directory(
+    root: &Path,
+    tracks: &mut Vec<NoiseTrack>,
+    seen: &mut HashSet<String>,
+) -> Result<(), AppError> {
+    let mut entries = walkdir::WalkDir::new(root)
+        .max_depth(2)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter();
+
+    while let Some(entry) = entries.next() {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => return Err(AppError::FileSystem(FileSystemError::DirectoryReadError)),
+        };
+        let path = entry.path();
+        if entry.depth() > 0 && path.is_dir() {
+            if let Some(track) = folder_track(&entry) {
+                if seen.insert(track.name.clone()) {
+                    tracks.push(track);
+                }
+                entries.skip_current_dir();
+            }
+            continue;
+        }
+        if !path.is_file() || path.has_extension(&[SIDECAR_EXTENSION]) {
+            continue;
+        }
+        if !path.has_extension(SUPPORTED_EXTENSIONS) {
+            return Err(AppError::FileSystem(FileSystemError::InvalidFileFormat));
+        }
+
+        let name = get_stem(path);
+        if seen.insert(name.clone()) {
+            let mut track = NoiseTrack::new(name, path.to_path_buf());
+            if entry.depth() == 2 {
+                track.category = path.parent().and_then(dir_name);
+            }
+            if let Some(sidecar) = read_sidecar(path) {
+                track.category = sidecar.category.or(track.category);
+                track.tags = sidecar.tags;
+            }
+            tracks.push(track);
+        }
+    }
+
+    Ok(())
+}
+
+fn dir_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+// Event or variation track of a folder with an `event.toml` or `variations.toml` file
+fn folder_track(entry: &walkdir::DirEntry) -> Option<NoiseTrack> {
+    let path = entry.path();
+    let (source, category, tags) = if let Some(sidecar) =
+        read_folder_sidecar::<EventSidecar>(path, EVENT_FILE)
+    {
+        let source = SoundSource::Events(path.to_path_buf(), sidecar.settings);
+        (source, sidecar.category, sidecar.tags)
+    } else if let Some(sidecar) = read_folder_sidecar::<VariationSidecar>(path, VARIATIONS_FILE) {
+        let source = SoundSource::Variations(path.to_path_buf(), sidecar.settings);
+        (source, sidecar.category, sidecar.tags)
+    } else {
+        return None;
+    };
+    let mut track = NoiseTrack::with_source(dir_name(path)?, source);
+
+    let parent_category = if entry.depth() == 2 {
+        path.parent().and_then(dir_name)
+    } else {
+        None
+    };
+    track.category = category.or(parent_category);
+    track.tags = tags;
+    Some(track)
+}
+
+// Read the settings file of a folder track, an invalid one falls back to the defaults
+fn read_folder_sidecar<T: DeserializeOwned + Default>(folder: &Path, file: &str) -> Option<T> {
+    let sidecar_path = folder.join(file);
+    let contents = std::fs::read_to_string(&sidecar_path).ok()?;
+
+    Some(
+        toml::from_str(&contents)
+            .inspect_err(|e| log::warn!("Ignoring invalid {}: {e}", sidecar_path.display()))
+            .unwrap_or_default(),
+    )
+}
+
+// Audio files directly inside a folder, by name
+pub fn folder_clips(folder: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut clips: Vec<PathBuf> = std::fs::read_dir(folder)
+        .map_err(|e| AppError::FileSystem(FileSystemError::IOError(e.to_string())))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.has_extension(SUPPORTED_EXTENSIONS))
+        .collect();
+    clips.sort();
+
+    if clips.is_empty() {
+        return Err(AppError::Audio(AudioError::PlaybackError(format!(
+            "No clips in {}",
+            folder.display()
+        ))));
+    }
+    Ok(clips)
+}
+
+// Read the optional `<track>.toml` file next to an audio file
+fn read_sidecar(path: &Path) -> Option<TrackSidecar> {
+    let sidecar_path = path.with_extension(SIDECAR_EXTENSION);
+    let contents = std::fs::read_to_string(&sidecar_path).ok()?;
+
+    toml::from_str(&contents)
+        .inspect_err(|e| log::warn!("Ignoring invalid {}: {e}", sidecar_path.display()))
+        .ok()
+}
+
 // checks if users .config contains directory cosmic-noise/sounds
 fn config_dir_exists() -> Option<PathBuf> {
     match dirs::config_local_dir() {
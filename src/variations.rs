@@ -0,0 +1,293 @@
+//! Variation tracks: one sound rotating through several recordings.
+//!
+//! A folder with a `variations.toml` file holds alternative takes of the same
+//! sound. They are played one at a time without looping: shortly before one
+//! ends, another is picked at random and crossfaded in, so a long session never
+//! repeats the same loop back to back.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use kira::sound::streaming::{StreamingSoundData, StreamingSoundHandle, StreamingSoundSettings};
+use kira::sound::{FromFileError, PlaybackState};
+use kira::{Panning, PlaybackRate, Tween};
+
+use crate::errors::{AppError, AudioError};
+use crate::models::VariationSettings;
+use crate::utils::files::folder_clips;
+
+// Which variation plays and when the next one starts
+pub struct Rotation {
+    count: usize,
+    current: usize,
+    crossfade: Duration,
+    // Overlap with the next variation, shortened for short files
+    fade: Duration,
+    // Time left until the next variation fades in
+    remaining: Duration,
+    rng: fastrand::Rng,
+}
+
+impl Rotation {
+    // Start on a random one of `count` variations
+    pub fn new(count: usize, crossfade: Duration, mut rng: fastrand::Rng) -> Self {
+        Self {
+            count,
+            current: rng.usize(..count.max(1)),
+            crossfade,
+            fade: crossfade,
+            remaining: Duration::ZERO,
+            rng,
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    // Crossfade into the variation returned by `advance`
+    pub fn fade(&self) -> Duration {
+        self.fade
+    }
+
+    // The current variation started playing and lasts `duration`
+    pub fn started(&mut self, duration: Duration) {
+        self.fade = self.crossfade.min(duration / 2);
+        self.remaining = duration - self.fade;
+    }
+
+    // The current variation now plays `factor` times as long, e.g. after a rate change
+    pub fn rescale(&mut self, factor: f64) {
+        self.remaining = self.remaining.mul_f64(factor);
+        self.fade = self.fade.mul_f64(factor);
+    }
+
+    // Let `elapsed` pass, returning the next variation once it is due
+    pub fn advance(&mut self, elapsed: Duration) -> Option<usize> {
+        self.remaining = self.remaining.saturating_sub(elapsed);
+        if !self.remaining.is_zero() {
+            return None;
+        }
+
+        // Never the same take twice in a row, unless there is only one
+        if self.count > 1 {
+            let next = self.rng.usize(..self.count - 1);
+            self.current = if next >= self.current { next + 1 } else { next };
+        }
+        Some(self.current)
+    }
+}
+
+fn crossfade_tween(duration: Duration) -> Tween {
+    Tween {
+        duration,
+        easing: kira::Easing::Linear,
+        start_time: kira::StartTime::Immediate,
+    }
+}
+
+// Streams of a playing variation track
+pub struct VariationLayer {
+    files: Vec<PathBuf>,
+    rotation: Rotation,
+    handle: StreamingSoundHandle<FromFileError>,
+    // Previous variation while it fades out
+    fading: Option<StreamingSoundHandle<FromFileError>>,
+    // Effective track volume in dB, pan and playback rate
+    volume: f32,
+    pan: f32,
+    rate: f32,
+    paused: bool,
+}
+
+impl VariationLayer {
+    // Start a random variation of the folder through `play`
+    pub fn start<E: fmt::Display>(
+        folder: &Path,
+        settings: VariationSettings,
+        volume: f32,
+        pan: f32,
+        rate: f32,
+        fade_in: Option<Tween>,
+        play: impl FnOnce(
+            StreamingSoundData<FromFileError>,
+        ) -> Result<StreamingSoundHandle<FromFileError>, E>,
+    ) -> Result<Self, AppError> {
+        let files = folder_clips(folder)?;
+        let mut rotation = Rotation::new(files.len(), settings.crossfade(), fastrand::Rng::new());
+        let (data, duration) = open(&files[rotation.current()], volume, pan, rate, fade_in)?;
+        let handle = play(data).map_err(|e| {
+            log::error!("Failed to play sound: {e}");
+            AppError::Audio(AudioError::HandleCreationFailed)
+        })?;
+        rotation.started(duration);
+
+        Ok(Self {
+            files,
+            rotation,
+            handle,
+            fading: None,
+            volume,
+            pan,
+            rate,
+            paused: false,
+        })
+    }
+
+    pub fn state(&self) -> PlaybackState {
+        self.handle.state()
+    }
+
+    // Let `elapsed` pass and crossfade into the next variation through `play` when due
+    pub fn advance<E: fmt::Display>(
+        &mut self,
+        elapsed: Duration,
+        play: impl FnOnce(
+            StreamingSoundData<FromFileError>,
+        ) -> Result<StreamingSoundHandle<FromFileError>, E>,
+    ) {
+        if self.paused {
+            return;
+        }
+        let Some(next) = self.rotation.advance(elapsed) else {
+            return;
+        };
+
+        // A file that fails is skipped, another one is tried on the next step
+        let path = &self.files[next];
+        let tween = crossfade_tween(self.rotation.fade());
+        let opened = open(path, self.volume, self.pan, self.rate, Some(tween));
+        let started = opened.and_then(|(data, duration)| {
+            play(data)
+                .map(|handle| (handle, duration))
+                .map_err(|e| AppError::Audio(AudioError::PlaybackError(e.to_string())))
+        });
+
+        match started {
+            Ok((handle, duration)) => {
+                let mut previous = std::mem::replace(&mut self.handle, handle);
+                previous.stop(tween);
+                if let Some(mut fading) = self.fading.replace(previous) {
+                    fading.stop(Tween::default());
+                }
+                self.rotation.started(duration);
+                log::debug!("Crossfading to variation {}", path.display());
+            }
+            Err(e) => log::error!("Failed to play variation {}: {e}", path.display()),
+        }
+    }
+
+    fn handles(&mut self) -> impl Iterator<Item = &mut StreamingSoundHandle<FromFileError>> {
+        std::iter::once(&mut self.handle).chain(self.fading.as_mut())
+    }
+
+    pub fn set_volume(&mut self, volume: f32, tween: Tween) {
+        self.volume = volume;
+        for handle in self.handles() {
+            handle.set_volume(volume, tween);
+        }
+    }
+
+    pub fn set_panning(&mut self, pan: f32, tween: Tween) {
+        self.pan = pan;
+        for handle in self.handles() {
+            handle.set_panning(Panning(pan), tween);
+        }
+    }
+
+    // The rest of the current take plays at the new rate, so the next crossfade moves with it
+    pub fn set_playback_rate(&mut self, rate: f32, tween: Tween) {
+        self.rotation.rescale(self.rate as f64 / rate as f64);
+        self.rate = rate;
+        for handle in self.handles() {
+            handle.set_playback_rate(PlaybackRate(rate as f64), tween);
+        }
+    }
+
+    pub fn pause(&mut self, tween: Tween) {
+        self.paused = true;
+        for handle in self.handles() {
+            handle.pause(tween);
+        }
+    }
+
+    pub fn resume(&mut self, tween: Tween) {
+        self.paused = false;
+        for handle in self.handles() {
+            handle.resume(tween);
+        }
+    }
+
+    pub fn stop(&mut self, tween: Tween) {
+        for handle in self.handles() {
+            handle.stop(tween);
+        }
+    }
+}
+
+// Decode the start of a variation, returning it with how long it plays at `rate`
+fn open(
+    path: &Path,
+    volume: f32,
+    pan: f32,
+    rate: f32,
+    fade_in: Option<Tween>,
+) -> Result<(StreamingSoundData<FromFileError>, Duration), AppError> {
+    let data = StreamingSoundData::from_file(path).map_err(|e| AppError::Audio(e.into()))?;
+    let duration = data.duration().div_f32(rate);
+    let settings = StreamingSoundSettings::new()
+        .fade_in_tween(fade_in)
+        .volume(volume)
+        .panning(Panning(pan))
+        .playback_rate(PlaybackRate(rate as f64));
+
+    Ok((data.with_settings(settings), duration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_timing() {
+        let mut rotation = Rotation::new(3, Duration::from_secs(5), fastrand::Rng::with_seed(7));
+        rotation.started(Duration::from_secs(60));
+
+        assert_eq!(rotation.advance(Duration::from_secs(54)), None);
+        let first = rotation.current();
+        let next = rotation.advance(Duration::from_secs(1)).unwrap();
+        assert_ne!(next, first);
+
+        // Short files overlap by half their length at most
+        rotation.started(Duration::from_secs(4));
+        assert_eq!(rotation.fade(), Duration::from_secs(2));
+        assert!(rotation.advance(Duration::from_secs(2)).is_some());
+    }
+
+    #[test]
+    fn test_rotation_rescale() {
+        let mut rotation = Rotation::new(3, Duration::from_secs(5), fastrand::Rng::with_seed(7));
+        rotation.started(Duration::from_secs(60));
+        assert_eq!(rotation.advance(Duration::from_secs(15)), None);
+
+        // Doubling the rate halves what is left of the take
+        rotation.rescale(0.5);
+        assert_eq!(rotation.fade(), Duration::from_millis(2500));
+        assert_eq!(rotation.advance(Duration::from_secs(19)), None);
+        assert!(rotation.advance(Duration::from_secs(1)).is_some());
+    }
+
+    #[test]
+    fn test_rotation_never_repeats() {
+        let mut rotation = Rotation::new(2, Duration::ZERO, fastrand::Rng::with_seed(1));
+        for _ in 0..10 {
+            let previous = rotation.current();
+            assert_ne!(rotation.advance(Duration::ZERO), Some(previous));
+        }
+
+        // A single take simply follows itself
+        let mut single = Rotation::new(1, Duration::ZERO, fastrand::Rng::with_seed(1));
+        assert_eq!(single.advance(Duration::ZERO), Some(0));
+    }
+}